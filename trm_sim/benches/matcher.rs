@@ -0,0 +1,56 @@
+//! Compares finding transitions through the jump table with the consume patterns.
+//! Run with `cargo bench --bench matcher`.
+
+use std::hint::black_box;
+use std::time::Instant;
+use trm_sim::trm::Machine;
+
+/// the symbols of the benchmarked machines
+const SYMBOLS: &str = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const INPUT_LEN: usize = 1 << 20;
+const ROUNDS: u32 = 5;
+
+/// a one tape machine rotating every symbol to the next one, a literal machine
+/// matched through the jump table. With `wildcard`, an unreachable state consuming
+/// a wildcard makes it matched through the consume patterns instead
+fn rotate_model(wildcard: bool) -> String {
+    let symbols = SYMBOLS.chars().collect::<Vec<_>>();
+    let mut model = String::from("[[state]]\nname = \"q0\"\nstart = true\nfinal = true\n");
+    for (i, c) in symbols.iter().enumerate() {
+        let next = symbols[(i + 1) % symbols.len()];
+        model += &format!(
+            "[[state.trans]]\ncons = \"{c}\"\nprod = \"{next}\"\nmove = \"R\"\nnext = \"q0\"\n"
+        );
+    }
+    if wildcard {
+        model += "[[state]]\nname = \"unused\"\n[[state.trans]]\n\
+                  cons = \"*\"\nprod = \"*\"\nmove = \"R\"\nnext = \"unused\"\n";
+    }
+    model
+}
+
+/// the best time per step of the machine over some rounds, in nanoseconds
+fn time_per_step(model: &str, input: &str) -> f64 {
+    let mut machine = Machine::new(model, "toml").unwrap();
+    let mut best = f64::INFINITY;
+    for _ in 0..ROUNDS {
+        machine.reset();
+        machine.input(input);
+        let start = Instant::now();
+        let accepted = machine.run().unwrap();
+        let elapsed = start.elapsed();
+        assert!(black_box(accepted));
+        assert_eq!(machine.steps(), INPUT_LEN);
+        best = best.min(elapsed.as_nanos() as f64 / INPUT_LEN as f64);
+    }
+    best
+}
+
+fn main() {
+    let input = SYMBOLS.chars().cycle().take(INPUT_LEN).collect::<String>();
+    let table = time_per_step(&rotate_model(false), &input);
+    let patterns = time_per_step(&rotate_model(true), &input);
+    println!("table:    {table:.2} ns/step");
+    println!("patterns: {patterns:.2} ns/step");
+    println!("speedup:  {:.2}x", patterns / table);
+}
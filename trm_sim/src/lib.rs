@@ -15,23 +15,40 @@
 //!
 //! ```json
 //! {
+//!    "version": u32?,
+//!    "include": [str]?,
 //!    "states": [
 //!       {
-//!         "name": str,
+//!         "name": str?,
 //!         "start": boolean?,
 //!         "final": boolean?,
+//!         "accept_if": str?,
+//!         "blank": char?,
 //!         "transitions": [
 //!          {
 //!            "cons": str,
-//!            "prod": str,
-//!            "move": str,
-//!            "next": str,
+//!            "prod": str?,
+//!            "move": (str | [str])?,
+//!            "next": (str | usize)?,
+//!            "weight": f64?,
+//!            "label": str?,
+//!            "note": str?,
+//!            "push": char?,
+//!            "pop": boolean?,
 //!         },
 //!         ...
 //!        ]
 //!      },
 //!     ...
-//!   ]
+//!   ],
+//!    "templates": [
+//!       { "param": str, "values": [str], "states": [state] },
+//!       ...
+//!   ]?,
+//!    "tests": [
+//!       { "input": str, "accept": boolean },
+//!       ...
+//!   ]?
 //! }
 //! ```
 //!
@@ -1,17 +1,45 @@
 //! This module is for pure turing machine simulation,
 //! gui and other stuff is in other modules
 
+mod accept_condition;
+mod dfa;
+mod difference;
+mod dot;
+mod fold;
+mod include;
+mod language;
 mod machine;
 mod machine_running_error;
+mod matcher;
+mod model_limits;
 mod pattern;
+mod random;
+mod rng;
+mod run_outcome;
+mod run_stats;
+mod snapshot;
 mod state;
+mod svg;
 mod syntax_error;
 mod tape;
+mod template;
 mod transition;
+mod validation_warning;
 
+pub use accept_condition::*;
+pub use dfa::*;
+pub use difference::*;
+pub use language::*;
 pub use machine::*;
+pub use machine_running_error::*;
+pub use model_limits::*;
 pub use pattern::*;
+pub use run_outcome::*;
+pub use run_stats::*;
+pub use snapshot::*;
 pub use state::*;
 pub use syntax_error::*;
 pub use tape::*;
+pub use template::*;
 pub use transition::*;
+pub use validation_warning::*;
@@ -0,0 +1,66 @@
+//! This module contains the extra conditions a final state may require to accept.
+
+use crate::trm::Tape;
+use std::fmt::{Display, Formatter};
+
+/// a condition checked when the machine halts in a final state,
+/// the input is only accepted if it holds.
+/// In a model it is written like `tape0_empty` or `head1_at_origin`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcceptCondition {
+    /// `tapeN_empty`, the tape holds only blanks
+    TapeEmpty(usize),
+    /// `headN_at_origin`, the head of the tape is at the outside index 0
+    HeadAtOrigin(usize),
+}
+
+impl AcceptCondition {
+    /// parse a condition as written in a model, `None` if it is not known
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::AcceptCondition;
+    /// assert_eq!(AcceptCondition::parse("tape0_empty"), Some(AcceptCondition::TapeEmpty(0)));
+    /// assert_eq!(AcceptCondition::parse("tape_empty"), None);
+    /// ```
+    pub fn parse(s: &str) -> Option<Self> {
+        let index = |prefix: &str, suffix: &str| {
+            s.strip_prefix(prefix)?
+                .strip_suffix(suffix)?
+                .parse::<usize>()
+                .ok()
+        };
+        index("tape", "_empty")
+            .map(AcceptCondition::TapeEmpty)
+            .or_else(|| index("head", "_at_origin").map(AcceptCondition::HeadAtOrigin))
+    }
+
+    /// the tape the condition is about
+    pub fn tape(&self) -> usize {
+        match self {
+            AcceptCondition::TapeEmpty(i) | AcceptCondition::HeadAtOrigin(i) => *i,
+        }
+    }
+
+    /// check the condition on the tapes of a halted machine,
+    /// `empty` written on a tape counts as blank
+    pub fn holds(&self, tapes: &[Tape], empty: char) -> bool {
+        let Some(tape) = tapes.get(self.tape()) else {
+            return false;
+        };
+        match self {
+            AcceptCondition::TapeEmpty(_) => {
+                tape.contents().iter().all(|c| c.is_none_or(|c| c == empty))
+            }
+            AcceptCondition::HeadAtOrigin(_) => tape.head() == 0,
+        }
+    }
+}
+
+impl Display for AcceptCondition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AcceptCondition::TapeEmpty(i) => write!(f, "tape{i}_empty"),
+            AcceptCondition::HeadAtOrigin(i) => write!(f, "head{i}_at_origin"),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! This module contains a lightweight finite automaton,
+//! extracted from machines that only scan their input.
+
+use crate::trm::Pattern;
+use std::collections::{HashMap, HashSet};
+use std::iter::repeat;
+
+/// the transitions of one dfa state, as pattern and next state
+pub(crate) type DfaTransitions = Vec<(Box<dyn Pattern>, String)>;
+
+/// a deterministic finite automaton over the symbols of one tape.
+/// Transitions are tried in declaration order, like the machine it comes from.
+#[derive(Clone)]
+pub struct Dfa {
+    /// the start state
+    pub(crate) start: String,
+    /// the final states
+    pub(crate) final_states: HashSet<String>,
+    /// the transitions of each state
+    pub(crate) transitions: HashMap<String, DfaTransitions>,
+}
+
+impl Dfa {
+    /// check if the automaton accepts the input,
+    /// giving the same verdict as running the machine it comes from.
+    /// After the input is consumed the automaton keeps reading blanks,
+    /// an input driving it into a cycle over blanks never halts and is not accepted.
+    pub fn accepts(&self, input: &str) -> bool {
+        let mut state = &self.start;
+        // states already entered while reading blanks
+        let mut blank_states = HashSet::new();
+        for symbol in input.chars().map(Some).chain(repeat(None)) {
+            if symbol.is_none() && !blank_states.insert(state) {
+                return false;
+            }
+            let next = self
+                .transitions
+                .get(state)
+                .and_then(|ts| ts.iter().find(|(p, _)| p.match_input(symbol)));
+            match next {
+                Some((_, next)) => state = next,
+                None => break,
+            }
+        }
+        self.final_states.contains(state)
+    }
+
+    /// get the start state
+    pub fn start_state(&self) -> &str {
+        &self.start
+    }
+
+    /// check if the state is a final state
+    pub fn is_final(&self, state: &str) -> bool {
+        self.final_states.contains(state)
+    }
+}
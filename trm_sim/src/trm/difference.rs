@@ -0,0 +1,113 @@
+//! This module contains the differences between two machine identifiers,
+//! to show how a final configuration is off from the expected one.
+
+use crate::trm::{FrozenTape, MachineIdentifier};
+use std::fmt::{Display, Formatter};
+
+/// one way an identifier differs from the expected one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// the machines are in different states
+    State { expected: String, actual: String },
+    /// the machines have different numbers of tapes,
+    /// only the tapes both have are compared
+    TapeCount { expected: usize, actual: usize },
+    /// the first cell of a tape that differs, by outside index.
+    /// `None` is a cell outside the frozen range
+    Cell {
+        tape: usize,
+        index: isize,
+        expected: Option<char>,
+        actual: Option<char>,
+    },
+    /// the heads of a tape are at different outside indexes
+    Head {
+        tape: usize,
+        expected: isize,
+        actual: isize,
+    },
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let show = |c: &Option<char>| c.map_or("nothing".to_string(), |c| format!("`{c}`"));
+        match self {
+            Difference::State { expected, actual } => {
+                write!(f, "State is `{actual}`, expected `{expected}`")
+            }
+            Difference::TapeCount { expected, actual } => {
+                write!(f, "Machine has {actual} tapes, expected {expected}")
+            }
+            Difference::Cell {
+                tape,
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Tape {tape} has {} at {index}, expected {}",
+                show(actual),
+                show(expected)
+            ),
+            Difference::Head {
+                tape,
+                expected,
+                actual,
+            } => write!(f, "Head {tape} is at {actual}, expected {expected}"),
+        }
+    }
+}
+
+/// the symbol of the frozen tape at the outside index
+fn cell(tape: &FrozenTape, index: isize) -> Option<char> {
+    usize::try_from(index - tape.range.start)
+        .ok()
+        .and_then(|i| tape.tape.chars().nth(i))
+}
+
+impl MachineIdentifier {
+    /// lists how this identifier differs from the expected one,
+    /// empty if they are the same.
+    /// The states and tape counts are compared first,
+    /// then for each tape the first differing cell and the head.
+    /// Frozen tapes keep the blank cell under the head,
+    /// so heads at different places may also show as a differing blank cell.
+    pub fn compare(&self, expected: &MachineIdentifier) -> Vec<Difference> {
+        let mut differences = vec![];
+        if self.current_state != expected.current_state {
+            differences.push(Difference::State {
+                expected: expected.current_state.clone(),
+                actual: self.current_state.clone(),
+            });
+        }
+        if self.tape.len() != expected.tape.len() {
+            differences.push(Difference::TapeCount {
+                expected: expected.tape.len(),
+                actual: self.tape.len(),
+            });
+        }
+        for (i, (actual, expected)) in self.tape.iter().zip(&expected.tape).enumerate() {
+            let start = actual.range.start.min(expected.range.start);
+            let end = actual.range.end.max(expected.range.end);
+            let first = (start..end)
+                .map(|index| (index, cell(expected, index), cell(actual, index)))
+                .find(|(_, expected, actual)| expected != actual);
+            if let Some((index, expected, actual)) = first {
+                differences.push(Difference::Cell {
+                    tape: i,
+                    index,
+                    expected,
+                    actual,
+                });
+            }
+            if actual.head != expected.head {
+                differences.push(Difference::Head {
+                    tape: i,
+                    expected: expected.head,
+                    actual: actual.head,
+                });
+            }
+        }
+        differences
+    }
+}
@@ -0,0 +1,91 @@
+//! This module contains the export of a machine to graphviz dot.
+
+use crate::trm::{Direction, Machine};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// quote a string as a dot id
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Machine {
+    /// export the state diagram as a graphviz dot graph.
+    /// States are sorted by name and edges follow the transition order,
+    /// each edge is labeled `cons/prod,move`
+    pub fn to_dot(&self) -> String {
+        self.dot(&HashMap::new())
+    }
+
+    /// export the state diagram like `to_dot`, with the edges taken by running
+    /// the input for at most `max_steps` steps colored red,
+    /// and the steps taking them appended to the label, like `0/1,R (1, 3)`.
+    /// The run uses a copy of the machine and stops at the first error
+    pub fn to_dot_with_run(&self, input: &str, max_steps: usize) -> String {
+        let mut machine = self.clone();
+        machine.reset();
+        machine.input(input);
+        let mut steps = HashMap::<_, Vec<usize>>::new();
+        for step in 1..=max_steps {
+            if !matches!(machine.run_once(), Ok(false)) {
+                break;
+            }
+            if let Some(taken) = machine.last_transition.clone() {
+                steps.entry(taken).or_default().push(step);
+            }
+        }
+        self.dot(&steps)
+    }
+
+    /// export the state diagram, with the steps taking each transition by state and index
+    fn dot(&self, steps: &HashMap<(String, usize), Vec<usize>>) -> String {
+        let mut names = self.states.keys().collect::<Vec<_>>();
+        names.sort();
+        let mut dot = String::from("digraph {\n    rankdir=LR;\n");
+        dot.push_str("    \"\" [shape=none];\n");
+        writeln!(dot, "    \"\" -> {};", quote(&self.start_state)).unwrap();
+        for name in &names {
+            let shape = if self.final_states.contains(*name) {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+            writeln!(dot, "    {} [shape={shape}];", quote(name)).unwrap();
+        }
+        for name in &names {
+            for (i, t) in self.states[*name].transitions.iter().enumerate() {
+                let config = &self.pattern_config;
+                let direction = t
+                    .direction
+                    .iter()
+                    .map(|d| match d {
+                        Direction::Left => 'L',
+                        Direction::Right => 'R',
+                        Direction::Stay => 'S',
+                    })
+                    .collect::<String>();
+                let mut label = format!(
+                    "{}/{},{direction}",
+                    t.cons_string(config),
+                    config.join_escapes(&t.produce, &t.produce_escaped),
+                );
+                let taken = steps.get(&(name.to_string(), i));
+                if let Some(taken) = taken {
+                    let taken = taken.iter().map(usize::to_string).collect::<Vec<_>>();
+                    write!(label, " ({})", taken.join(", ")).unwrap();
+                }
+                let color = if taken.is_some() { ", color=red" } else { "" };
+                writeln!(
+                    dot,
+                    "    {} -> {} [label={}{color}];",
+                    quote(name),
+                    quote(&t.next_state_name),
+                    quote(&label)
+                )
+                .unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
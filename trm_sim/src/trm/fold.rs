@@ -0,0 +1,111 @@
+//! This module contains folding literal transitions with the same effect
+//! into one transition consuming a class of symbols.
+
+use crate::trm::matcher::Matcher;
+use crate::trm::{ClassPattern, Machine, Pattern, PatternConfig, Transition};
+
+/// check if the transition reads and writes only literals, and has nothing
+/// a folded transition could not keep, like a weight, label or note
+fn is_foldable(t: &Transition, config: &PatternConfig) -> bool {
+    let literal = |c: char, escaped: bool| escaped || !config.is_meta(c);
+    t.consume.len() == 1
+        && literal(t.consume[0], t.consume_escaped[0])
+        && literal(t.produce[0], t.produce_escaped[0])
+        && t.weight.is_none()
+        && t.label.is_none()
+        && t.note.is_none()
+        && t.folded.is_empty()
+}
+
+/// check if the two transitions do the same once matched,
+/// keeping the symbol read or writing the same one, moving the same way to the same state
+fn same_effect(a: &Transition, b: &Transition) -> bool {
+    let keeps = |t: &Transition| t.consume == t.produce;
+    let effect = if keeps(a) {
+        keeps(b)
+    } else {
+        !keeps(b) && a.produce == b.produce && a.produce_escaped == b.produce_escaped
+    };
+    effect && a.direction == b.direction && a.next_state_name == b.next_state_name
+}
+
+impl Machine {
+    /// folds the literal transitions of each state that only differ in the symbol
+    /// they consume into one transition consuming a class of these symbols,
+    /// like `0 -> 0` and `1 -> 1` moving right to `q1` into `[01] -> keep`.
+    /// A group is only folded if no other transition of the state matches any of its symbols,
+    /// so the same transition is taken as before for every head.
+    /// Only one tape machines are folded, and the transition taken by the last step is forgotten.
+    /// The model of the machine lists the folded transitions again one by one,
+    /// all in the place of the first of them.
+    /// Returns the number of transitions removed.
+    pub fn fold_transitions(&mut self) -> usize {
+        if self.tape_count() != 1 {
+            return 0;
+        }
+        let mut removed = 0;
+        for state in self.states.values_mut() {
+            let transitions = std::mem::take(&mut state.transitions);
+            let mut folded_into = vec![None; transitions.len()];
+            for (i, t) in transitions.iter().enumerate() {
+                if folded_into[i].is_some() || !is_foldable(t, &self.pattern_config) {
+                    continue;
+                }
+                let group = (i..transitions.len())
+                    .filter(|&j| folded_into[j].is_none())
+                    .filter(|&j| {
+                        let u = &transitions[j];
+                        is_foldable(u, &self.pattern_config) && same_effect(t, u)
+                    })
+                    .collect::<Vec<_>>();
+                let clashes = transitions.iter().enumerate().any(|(k, other)| {
+                    !group.contains(&k)
+                        && group.iter().any(|&j| {
+                            other.consume_pattern[0].match_input(Some(transitions[j].consume[0]))
+                        })
+                });
+                if group.len() > 1 && !clashes {
+                    for &j in &group {
+                        folded_into[j] = Some(i);
+                    }
+                }
+            }
+            for (i, t) in transitions.iter().enumerate() {
+                match folded_into[i] {
+                    None => state.transitions.push(t.clone()),
+                    Some(first) if first == i => {
+                        let group = (i..transitions.len())
+                            .filter(|&j| folded_into[j] == Some(i))
+                            .map(|j| transitions[j].clone())
+                            .collect::<Vec<_>>();
+                        removed += group.len() - 1;
+                        let mut symbols = vec![];
+                        for c in group.iter().map(|t| t.consume[0]) {
+                            if !symbols.contains(&c) {
+                                symbols.push(c);
+                            }
+                        }
+                        let pattern = ClassPattern {
+                            symbols,
+                            keep: t.consume == t.produce,
+                        };
+                        state.transitions.push(Transition {
+                            consume_pattern: vec![Box::new(pattern) as Box<dyn Pattern>],
+                            consume_regex: None,
+                            folded: group,
+                            ..t.clone()
+                        });
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        if removed > 0 {
+            // the consumed chars of a folded transition are not all it matches
+            self.matcher = Matcher::Patterns;
+            // the indexes of the transitions moved
+            self.last_transition = None;
+        }
+        removed
+    }
+}
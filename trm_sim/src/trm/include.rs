@@ -0,0 +1,153 @@
+//! This module contains loading a model from a file,
+//! merging the files it includes.
+//! With the `gzip` feature, gzip compressed files are read too.
+
+use crate::trm::{Machine, MachineModel, ModelLimits, SyntaxError, SyntaxErrorType};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+impl Machine {
+    /// builds the machine of the model file at `path`, with the files it includes merged,
+    /// the format is the file extension
+    /// # Errors
+    /// * `SyntaxError` - if a file is not read, the includes are not valid,
+    ///   or the merged model is not valid
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, SyntaxError> {
+        Machine::from_model(MachineModel::from_path(path)?)
+    }
+}
+
+impl MachineModel {
+    /// reads the model file at `path`, the format is the file extension.
+    /// A gzip compressed file, `.gz` or starting with the gzip magic bytes,
+    /// is decompressed with the `gzip` feature, the format is the extension before `.gz`.
+    /// The files of `include`, relative to the including file, are merged into it,
+    /// each file once, even if it is included by several files
+    /// # Errors
+    /// * `FileNotRead` - if a file is not read, has no extension,
+    ///   or is compressed without the `gzip` feature
+    /// * `IncludeNotValid` - if the includes form a cycle, or define a state twice
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, SyntaxError> {
+        let mut loaded = HashSet::new();
+        Self::load(path.as_ref(), &mut vec![], &mut loaded)
+    }
+
+    /// merges the states and tests of `other` into this model, keeping this config.
+    /// The templates are expanded and states referred to by position are resolved
+    /// in each model first
+    /// # Errors
+    /// * `IncludeNotValid` - if both models define a state of the same name
+    /// * `TemplateNotValid` - if a template of either model is not valid
+    pub fn merge(&mut self, mut other: MachineModel) -> Result<(), SyntaxError> {
+        self.expand_templates(&ModelLimits::default())?;
+        other.expand_templates(&ModelLimits::default())?;
+        self.resolve_names()?;
+        other.resolve_names()?;
+        if let Some(state) = other
+            .state
+            .iter()
+            .find(|s| self.state.iter().any(|t| t.name() == s.name()))
+        {
+            return Err(SyntaxError::new(
+                SyntaxErrorType::IncludeNotValid,
+                format!("state `{}` is defined in more than one model", state.name()),
+            ));
+        }
+        self.state.append(&mut other.state);
+        self.tests.append(&mut other.tests);
+        Ok(())
+    }
+
+    /// reads the file and merges its includes,
+    /// `including` are the files on the way to it, to find cycles
+    fn load(
+        path: &Path,
+        including: &mut Vec<PathBuf>,
+        loaded: &mut HashSet<PathBuf>,
+    ) -> Result<Self, SyntaxError> {
+        let error = |message: String| SyntaxError::new(SyntaxErrorType::FileNotRead, message);
+        let canonical = path.canonicalize().map_err(|e| {
+            error(format!("model file `{}` not found", path.display())).with_source(e)
+        })?;
+        if including.contains(&canonical) {
+            let cycle = including
+                .iter()
+                .chain([&canonical])
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>();
+            return Err(SyntaxError::new(
+                SyntaxErrorType::IncludeNotValid,
+                format!("the includes form a cycle {}", cycle.join(" -> ")),
+            ));
+        }
+        let (text, fmt) = Self::read(path)?;
+        let mut model = MachineModel::from_str(&text, &fmt)?;
+        loaded.insert(canonical.clone());
+        including.push(canonical);
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for include in std::mem::take(&mut model.include) {
+            let included = dir.join(include);
+            let skip = included
+                .canonicalize()
+                .is_ok_and(|p| loaded.contains(&p) && !including.contains(&p));
+            if !skip {
+                model.merge(Self::load(&included, including, loaded)?)?;
+            }
+        }
+        including.pop();
+        Ok(model)
+    }
+
+    /// reads the text of the file, decompressed if needed, and its format
+    fn read(path: &Path) -> Result<(String, String), SyntaxError> {
+        let error = |message: String| SyntaxError::new(SyntaxErrorType::FileNotRead, message);
+        let bytes = std::fs::read(path).map_err(|e| {
+            error(format!("model file `{}` not read", path.display())).with_source(e)
+        })?;
+        let suffixed = path.extension().is_some_and(|e| e == "gz");
+        let bytes = if suffixed || bytes.starts_with(&[0x1f, 0x8b]) {
+            gunzip(&bytes, path)?
+        } else {
+            bytes
+        };
+        let name = if suffixed {
+            path.file_stem()
+        } else {
+            path.file_name()
+        };
+        let fmt = name
+            .and_then(|name| Path::new(name).extension())
+            .and_then(|e| e.to_str())
+            .ok_or_else(|| error(format!("model file `{}` has no extension", path.display())))?;
+        let text = String::from_utf8(bytes).map_err(|e| {
+            error(format!("model file `{}` is not utf-8", path.display())).with_source(e)
+        })?;
+        Ok((text, fmt.to_string()))
+    }
+}
+
+/// decompress a gzip compressed model file
+#[cfg(feature = "gzip")]
+fn gunzip(bytes: &[u8], path: &Path) -> Result<Vec<u8>, SyntaxError> {
+    use std::io::Read;
+    let mut out = vec![];
+    flate2::read::GzDecoder::new(bytes)
+        .read_to_end(&mut out)
+        .map_err(|e| {
+            let message = format!("model file `{}` is not valid gzip", path.display());
+            SyntaxError::new(SyntaxErrorType::FileNotRead, message).with_source(e)
+        })?;
+    Ok(out)
+}
+
+/// compressed model files are only read with the `gzip` feature
+#[cfg(not(feature = "gzip"))]
+fn gunzip(_bytes: &[u8], path: &Path) -> Result<Vec<u8>, SyntaxError> {
+    Err(SyntaxError::new(
+        SyntaxErrorType::FileNotRead,
+        format!(
+            "model file `{}` is gzip compressed, which needs the `gzip` feature",
+            path.display()
+        ),
+    ))
+}
@@ -0,0 +1,165 @@
+//! This module contains queries about the language a machine accepts,
+//! by running it on every input up to a length or on given examples.
+
+use crate::trm::{Machine, MachineIdentifier, RunOutcome};
+use std::io::BufRead;
+
+/// an example whose run did not end as expected
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    /// the input of the example
+    pub input: String,
+    /// the expected outcome
+    pub expected: RunOutcome,
+    /// the actual outcome, `None` if the run failed with an error
+    pub actual: Option<RunOutcome>,
+    /// the machine when the run ended
+    pub identifier: MachineIdentifier,
+}
+
+/// an input the two compared machines disagree on
+#[derive(Debug, Clone)]
+pub struct Counterexample {
+    /// the input both machines ran on
+    pub input: String,
+    /// the outcome of the first machine, `None` if the run failed with an error
+    pub this: Option<RunOutcome>,
+    /// the outcome of the other machine, `None` if the run failed with an error
+    pub other: Option<RunOutcome>,
+}
+
+/// how many runs of `run_lines` ended each way
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct RunTally {
+    /// the runs accepted
+    pub accepted: usize,
+    /// the runs rejected
+    pub rejected: usize,
+    /// the runs not halting within the step limit
+    pub timed_out: usize,
+    /// the runs failing with an error
+    pub errors: usize,
+}
+
+/// all strings over the alphabet, shortest first,
+/// duplicated symbols of the alphabet are ignored
+pub(crate) fn inputs_up_to(alphabet: &[char], max_len: usize) -> impl Iterator<Item = String> {
+    let mut symbols = alphabet.to_vec();
+    symbols.sort();
+    symbols.dedup();
+    let mut layer = vec![String::new()];
+    (0..=max_len).flat_map(move |len| {
+        if len > 0 {
+            layer = layer
+                .iter()
+                .flat_map(|s| symbols.iter().map(move |c| format!("{s}{c}")))
+                .collect();
+        }
+        layer.clone()
+    })
+}
+
+impl Machine {
+    /// returns all inputs over the alphabet up to `max_len` the machine accepts,
+    /// shortest first. Each input runs for at most `max_steps` steps,
+    /// inputs not accepted within the limit or failing with an error are left out.
+    /// The machine itself is not changed, the runs use a copy of it.
+    pub fn accepted_strings(
+        &self,
+        alphabet: &[char],
+        max_len: usize,
+        max_steps: usize,
+    ) -> Vec<String> {
+        let mut machine = self.clone();
+        inputs_up_to(alphabet, max_len)
+            .filter(|input| {
+                machine.reset();
+                machine.input(input);
+                matches!(machine.run_with_limit(max_steps), Ok(RunOutcome::Accepted))
+            })
+            .collect()
+    }
+
+    /// runs every example for at most `max_steps` steps,
+    /// and returns the examples not ending with the expected outcome, in order.
+    /// The machine itself is not changed, the runs use a copy of it.
+    pub fn check_examples(
+        &self,
+        examples: &[(&str, RunOutcome)],
+        max_steps: usize,
+    ) -> Result<(), Vec<Mismatch>> {
+        let mut machine = self.clone();
+        let mismatches = examples
+            .iter()
+            .filter_map(|(input, expected)| {
+                machine.reset();
+                machine.input(input);
+                let actual = machine.run_with_limit(max_steps).ok();
+                (actual != Some(*expected)).then(|| Mismatch {
+                    input: input.to_string(),
+                    expected: *expected,
+                    actual,
+                    identifier: machine.identifier(),
+                })
+            })
+            .collect::<Vec<_>>();
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatches)
+        }
+    }
+
+    /// runs every line of the reader as an input for at most `max_steps` steps,
+    /// reading one line at a time, and counts how the runs ended.
+    /// Line endings are not part of the input, the machine is reset before each run
+    /// and left as the last run ended.
+    /// # Errors
+    /// * `io::Error` - if a line cannot be read, the runs so far are lost
+    pub fn run_lines(
+        &mut self,
+        reader: impl BufRead,
+        max_steps: usize,
+    ) -> std::io::Result<RunTally> {
+        let mut tally = RunTally::default();
+        for line in reader.lines() {
+            self.reset();
+            self.input(&line?);
+            match self.run_with_limit(max_steps) {
+                Ok(RunOutcome::Accepted) => tally.accepted += 1,
+                Ok(RunOutcome::StepLimitReached) => tally.timed_out += 1,
+                Ok(_) => tally.rejected += 1,
+                Err(_) => tally.errors += 1,
+            }
+        }
+        Ok(tally)
+    }
+
+    /// runs both machines on every input over the alphabet up to `max_len`,
+    /// shortest first, for at most `max_steps` steps each,
+    /// and returns the first input their outcomes differ on.
+    /// The machine itself is not changed, the runs use a copy of it,
+    /// the other machine is left as it was after its last run.
+    pub fn equivalent_up_to(
+        &self,
+        other: &mut Machine,
+        alphabet: &[char],
+        max_len: usize,
+        max_steps: usize,
+    ) -> Result<(), Counterexample> {
+        let mut machine = self.clone();
+        let outcome = |machine: &mut Machine, input: &str| {
+            machine.reset();
+            machine.input(input);
+            machine.run_with_limit(max_steps).ok()
+        };
+        for input in inputs_up_to(alphabet, max_len) {
+            let this = outcome(&mut machine, &input);
+            let other = outcome(other, &input);
+            if this != other {
+                return Err(Counterexample { input, this, other });
+            }
+        }
+        Ok(())
+    }
+}
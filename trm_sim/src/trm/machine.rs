@@ -1,13 +1,20 @@
 //! This module contains the turing machine struct and its methods.
 
 use crate::trm::machine_running_error::MachineRunningError;
-use crate::trm::{PatternAction, PatternConfig};
+use crate::trm::matcher::{overlaps, Matcher};
+use crate::trm::rng::SplitMix64;
+use crate::trm::tape::FrozenCache;
+use crate::trm::transition::auto_name;
+use crate::trm::{AnyPattern, PatternAction, PatternConfig};
+use crate::trm::{Dfa, RunOutcome, RunStats};
+use crate::trm::{Direction, State, StateSerde, States, Transition};
 use crate::trm::{FrozenTape, Tape};
-use crate::trm::{State, StateSerde, Transition};
+use crate::trm::{ModelLimits, StateTemplate, ValidationWarning};
 use crate::trm::{SyntaxError, SyntaxErrorType};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::iter::zip;
+use std::time::{Duration, Instant};
 
 /// A turing machine struct
 /// # Example
@@ -66,32 +73,98 @@ use std::iter::zip;
 /// # Ok(())
 /// # }
 /// ```
+#[derive(Clone)]
 pub struct Machine {
     /// the states of the machine
-    states: HashMap<String, State>,
+    pub(crate) states: States,
     /// the start state of the machine
-    start_state: String,
+    pub(crate) start_state: String,
     /// the final states of the machine
-    final_states: HashSet<String>,
+    pub(crate) final_states: HashSet<String>,
     /// the current state
-    current_state: String,
+    pub(crate) current_state: String,
+    /// the id of the state the last step went to,
+    /// only used while it is still the current state
+    current_id: usize,
     /// the tapes of the machine
-    tape: Vec<Tape>,
+    pub(crate) tape: Vec<Tape>,
     /// the number of tapes
     tape_num: usize,
+    /// the outside index right after the last input symbol on the first tape
+    pub(crate) input_end: isize,
+    /// if a transition was taken with the first head past the last input symbol
+    pub(crate) read_past_input: bool,
+    /// the steps taken since the last reset
+    pub(crate) steps: usize,
+    /// the index of the transition taken in its state at each step since the last reset,
+    /// `None` if not recorded
+    pub(crate) choices: Option<Vec<usize>>,
     /// config for pattern matching
-    pattern_config: PatternConfig,
+    pub(crate) pattern_config: PatternConfig,
+    /// statistics of the current run, `None` if not recorded
+    stats: Option<RunStats>,
+    /// the tests declared in the model
+    tests: Vec<ModelTest>,
+    /// the state and index of the transition taken by the last step
+    pub(crate) last_transition: Option<(String, usize)>,
+    /// how heads are matched against transitions,
+    /// chosen from the transitions when the machine is built
+    pub(crate) matcher: Matcher,
+    /// if at most one transition of every state matches any heads,
+    /// computed when the machine is built
+    pub(crate) deterministic: bool,
+    /// the text the machine was built from, kept by `new_preserving`
+    source: Option<ModelSource>,
+    /// the last frozen view of every tape, followed step by step
+    /// so `identifier` does not freeze the whole tapes again
+    frozen: FrozenCache,
 }
 
+/// the text of a model kept to save it again unchanged
+#[derive(Debug, Clone)]
+struct ModelSource {
+    /// the text as given
+    text: String,
+    /// the format of the text
+    fmt: String,
+    /// the model of the machine when it was built, as json
+    model: String,
+}
+
+/// The newest model format version this crate understands
+pub const MODEL_VERSION: u32 = 1;
+
 /// A helper struct of machine model for serde
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MachineModel {
+    /// the format version of the model, `None` means the current version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<u32>,
+    /// the model files merged into this one, relative to its own file,
+    /// resolved by `from_path`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) include: Vec<String>,
     /// the states of the machine
     #[serde(default, alias = "states")]
-    state: Vec<StateSerde>,
+    pub(crate) state: Vec<StateSerde>,
+    /// states instantiated once per value of a parameter, expanded when the machine is built
+    #[serde(default, alias = "templates", skip_serializing_if = "Vec::is_empty")]
+    pub(crate) template: Vec<StateTemplate>,
     /// config for pattern matching
     #[serde(default, rename = "config")]
-    pattern_config: PatternConfig,
+    pub(crate) pattern_config: PatternConfig,
+    /// inputs with the expected verdict, for checking the machine
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) tests: Vec<ModelTest>,
+}
+
+/// one test declared in a model
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelTest {
+    /// the input of the test
+    pub input: String,
+    /// if the machine should accept the input
+    pub accept: bool,
 }
 
 /// Readonly identifier for one machine,
@@ -102,6 +175,10 @@ pub struct MachineIdentifier {
     pub current_state: String,
     /// current tape content
     pub tape: Vec<FrozenTape>,
+    /// how far each head moved in the last step, `-1`, `0` or `1`,
+    /// empty before the first step
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub last_move: Vec<i32>,
 }
 
 impl Machine {
@@ -115,19 +192,126 @@ impl Machine {
     pub fn new(model: &str, fmt: &str) -> Result<Self, SyntaxError> {
         // deserialize model
         let model = MachineModel::from_str(model, fmt)?;
+        Machine::from_model(model)
+    }
+
+    /// Creates a new machine from a model like `new`,
+    /// but unknown fields in the model are errors instead of being ignored.
+    /// # Arguments
+    /// * `model` - the model of the machine
+    /// * `fmt` - the format of the model
+    /// # Errors
+    /// * `SyntaxError` - if the model is not valid or has unknown fields
+    pub fn new_strict(model: &str, fmt: &str) -> Result<Self, SyntaxError> {
+        let model = MachineModel::from_str_strict(model, fmt)?;
+        Machine::from_model(model)
+    }
+
+    /// Creates a new machine from a model like `new`,
+    /// but a model larger than the limits is an error before any state is built.
+    /// # Errors
+    /// * `SyntaxError` - if the model is not valid, or `ModelLimitExceeded` if it is too large
+    pub fn new_with_limits(
+        model: &str,
+        fmt: &str,
+        limits: ModelLimits,
+    ) -> Result<Self, SyntaxError> {
+        let mut model = MachineModel::from_str(model, fmt)?;
+        model.expand_templates(&limits)?;
+        model.check_limits(&limits)?;
+        Machine::from_model(model)
+    }
+
+    /// Creates a new machine from a model like `new`,
+    /// keeping the text so `save` gives it back byte for byte
+    /// while the model of the machine is unchanged, comments and order included.
+    /// # Errors
+    /// * `SyntaxError` - if the model is not valid
+    pub fn new_preserving(model: &str, fmt: &str) -> Result<Self, SyntaxError> {
+        let mut machine = Machine::new(model, fmt)?;
+        machine.source = Some(ModelSource {
+            text: model.to_string(),
+            fmt: fmt.to_string(),
+            model: machine.model().to_text("json")?,
+        });
+        Ok(machine)
+    }
+
+    /// returns the model of the machine as text in the given format.
+    /// A machine from `new_preserving` gives back its original text
+    /// if the format is the same and its model did not change,
+    /// otherwise the model is serialized again
+    /// # Errors
+    /// * `SyntaxError` - if the format is not supported
+    pub fn save(&self, fmt: &str) -> Result<String, SyntaxError> {
+        let model = self.model();
+        if let Some(source) = &self.source {
+            if source.fmt == fmt && source.model == model.to_text("json")? {
+                return Ok(source.text.clone());
+            }
+        }
+        model.to_text(fmt)
+    }
+
+    /// Creates a new machine from a deserialized model,
+    /// like a model built from a parsed `serde_json::Value`.
+    /// # Errors
+    /// * `SyntaxError` - if the model is not valid
+    pub fn from_model(mut model: MachineModel) -> Result<Self, SyntaxError> {
+        if let Some(include) = model.include.first() {
+            return Err(SyntaxError::new(
+                SyntaxErrorType::IncludeNotValid,
+                format!("the model includes `{include}`, which only `from_path` resolves"),
+            ));
+        }
+        model.pattern_config.check_escape()?;
+        model.expand_templates(&ModelLimits::default())?;
+        model.resolve_names()?;
+        // compile stack effects, with the states writing pushed symbols
+        let mut pushing = vec![];
+        for state in &mut model.state {
+            for added in state.expand_stack(&model.pattern_config)? {
+                if pushing
+                    .iter()
+                    .all(|s: &StateSerde| s.name() != added.name())
+                {
+                    pushing.push(added);
+                }
+            }
+        }
+        if let Some(added) = pushing
+            .iter()
+            .find(|added| model.state.iter().any(|s| s.name() == added.name()))
+        {
+            return Err(SyntaxError::new(
+                SyntaxErrorType::StateNameDuplicated,
+                format!(
+                    "state `{}` is defined twice, a transition pushing to the stack adds it",
+                    added.name()
+                ),
+            ));
+        }
+        model.state.extend(pushing);
         // create states
-        let states: HashMap<_, _> = model
+        let mut states: HashMap<_, _> = model
             .state
             .into_iter()
-            .map(|s| State::try_from_serde(s, model.pattern_config))
+            .map(|s| State::try_from_serde(s, &model.pattern_config))
             .map(|state| state.map(|s| (s.name.clone(), s)))
             .collect::<Result<_, _>>()?;
         // filter start state and final states
-        let start_state = states
+        let mut start_state = states
             .iter()
             .filter(|(_, state)| state.is_start)
             .map(|(name, _)| name.clone())
             .collect::<Vec<String>>();
+        if start_state.is_empty() && model.pattern_config.infer_start {
+            let name = Machine::infer_start_state(&states)?;
+            if let Some(state) = states.get_mut(&name) {
+                state.is_start = true;
+            }
+            start_state.push(name);
+        }
         let final_states = states
             .iter()
             .filter(|(_, state)| state.is_final)
@@ -136,24 +320,173 @@ impl Machine {
 
         // check start state
         if start_state.len() != 1 {
-            return Err(SyntaxError {
-                error_type: SyntaxErrorType::StartStateError,
-                message: format!("start state error: {start_state:#?}"),
-            });
+            let mut message = if start_state.is_empty() {
+                "no start state, mark the state to start from with `start = true`".to_string()
+            } else {
+                start_state.sort();
+                format!(
+                    "more than one start state {start_state:?}, only one state may have `start = true`"
+                )
+            };
+            if final_states.is_empty() {
+                message.push_str(
+                    ", there is no final state either, mark accepting states with `final = true`",
+                );
+            }
+            return Err(SyntaxError::new(SyntaxErrorType::StartStateError, message));
         }
 
+        if model.pattern_config.sort_transitions {
+            for state in states.values_mut() {
+                // stable, equal transitions keep their declaration order
+                state.transitions.sort_by(|a, b| {
+                    (&a.consume, &a.next_state_name).cmp(&(&b.consume, &b.next_state_name))
+                });
+            }
+        }
+
+        if model.pattern_config.pad_tapes {
+            Machine::pad_tapes(&mut states, &model.pattern_config)?;
+        }
+        let tape_num = Machine::check_tape_num(&states)?;
+        let literal_only = states
+            .values()
+            .flat_map(|s| &s.transitions)
+            .flat_map(|t| zip(&t.consume, &t.consume_escaped))
+            .all(|(c, escaped)| *escaped || !model.pattern_config.is_meta(*c));
+        let states = States::new(states);
+        let matcher = Matcher::new(&states, literal_only, tape_num);
+        let deterministic = states.values().all(|s| {
+            s.transitions
+                .iter()
+                .enumerate()
+                .all(|(i, a)| s.transitions[i + 1..].iter().all(|b| !overlaps(a, b)))
+        });
+
         let machine = Machine {
             states,
             start_state: start_state[0].clone(),
             final_states,
             current_state: start_state[0].clone(),
+            current_id: 0,
             tape: Vec::new(),
-            tape_num: 0,
+            tape_num,
+            input_end: 0,
+            read_past_input: false,
+            steps: 0,
+            choices: None,
             pattern_config: model.pattern_config,
+            stats: None,
+            tests: model.tests,
+            last_transition: None,
+            matcher,
+            deterministic,
+            source: None,
+            frozen: FrozenCache::default(),
         };
         Ok(machine)
     }
 
+    /// find the only state without incoming transitions,
+    /// self loops are not counted as incoming.
+    fn infer_start_state(states: &HashMap<String, State>) -> Result<String, SyntaxError> {
+        let targets = states
+            .values()
+            .flat_map(|s| {
+                s.transitions
+                    .iter()
+                    .map(|t| &t.next_state_name)
+                    .filter(move |next| **next != s.name)
+            })
+            .collect::<HashSet<_>>();
+        let mut candidates = states
+            .keys()
+            .filter(|name| !targets.contains(name))
+            .cloned()
+            .collect::<Vec<String>>();
+        candidates.sort();
+        match &candidates[..] {
+            [name] => Ok(name.clone()),
+            _ => Err(SyntaxError::new(
+                SyntaxErrorType::StartStateError,
+                format!(
+                    "cannot infer start state, states without incoming transitions: {candidates:?}"
+                ),
+            )),
+        }
+    }
+
+    /// extend the transitions consuming fewer tapes than the others,
+    /// the trailing tapes match `any`, keep their symbol and stay
+    fn pad_tapes(
+        states: &mut HashMap<String, State>,
+        config: &PatternConfig,
+    ) -> Result<(), SyntaxError> {
+        let tape_num = states
+            .values()
+            .flat_map(|s| &s.transitions)
+            .map(|t| t.consume.len())
+            .max()
+            .unwrap_or(1);
+        for state in states.values_mut() {
+            for t in &mut state.transitions {
+                let missing = tape_num - t.consume.len();
+                if missing == 0 {
+                    continue;
+                }
+                let any = config.any.ok_or_else(|| {
+                    SyntaxError::new(
+                        SyntaxErrorType::TransitionTapeNumberNotMatch,
+                        format!(
+                            "{} of state `{}` consumes {} tapes of {tape_num}, \
+                             padding the other tapes needs `any` enabled",
+                            t.describe(),
+                            state.name,
+                            t.consume.len()
+                        ),
+                    )
+                })?;
+                for _ in 0..missing {
+                    t.consume.push(any);
+                    t.consume_escaped.push(false);
+                    t.consume_pattern.push(Box::new(AnyPattern));
+                    t.produce.push(any);
+                    t.produce_escaped.push(false);
+                    t.direction.push(Direction::Stay);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// infer the number of tapes from the widest transition,
+    /// and check every transition consumes exactly that many tapes.
+    /// A machine without transitions has one tape,
+    /// transitions consuming no tape at all are rejected.
+    fn check_tape_num(states: &HashMap<String, State>) -> Result<usize, SyntaxError> {
+        let transitions = || {
+            states
+                .values()
+                .flat_map(|s| s.transitions.iter().map(|t| (&s.name, t)))
+        };
+        let tape_num = transitions()
+            .map(|(_, t)| t.consume.len())
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        match transitions().find(|(_, t)| t.consume.len() != tape_num) {
+            Some((name, t)) => Err(SyntaxError::new(
+                SyntaxErrorType::TransitionTapeNumberNotMatch,
+                format!(
+                    "{} of state `{name}` consumes {} tapes, but the machine has {tape_num} tapes",
+                    t.describe(),
+                    t.consume.len()
+                ),
+            )),
+            None => Ok(tape_num),
+        }
+    }
+
     /// Resets the machine to the start state,
     /// and clears the tapes.
     /// # Errors
@@ -161,140 +494,1535 @@ impl Machine {
     pub fn reset(&mut self) {
         self.current_state = self.start_state.clone();
         self.tape.clear();
+        self.last_transition = None;
+        self.read_past_input = false;
+        self.steps = 0;
+        if let Some(choices) = &mut self.choices {
+            choices.clear();
+        }
+        if let Some(stats) = &mut self.stats {
+            *stats = RunStats::default();
+        }
+    }
+
+    /// enables or disables recording of run statistics,
+    /// recording is disabled by default to avoid the overhead.
+    /// Enabling it starts a fresh record.
+    pub fn record_stats(&mut self, enable: bool) {
+        self.stats = enable.then(RunStats::default);
+    }
+
+    /// enables or disables recording of the transition choices for `replay`,
+    /// recording is disabled by default so a long run keeps constant memory.
+    /// Enabling it starts a fresh record.
+    pub fn record_choices(&mut self, enable: bool) {
+        self.choices = enable.then(Vec::new);
+    }
+
+    /// returns the statistics recorded since the last reset,
+    /// `None` if recording is disabled
+    pub fn stats(&self) -> Option<&RunStats> {
+        self.stats.as_ref()
     }
 
     /// returns the identifier of the machine
     pub fn identifier(&self) -> MachineIdentifier {
         MachineIdentifier {
-            tape: self.tape.iter().map(|t| t.freeze(self.pattern_config.empty)).collect(),
+            tape: self.frozen.get(&self.tape, self.pattern_config.empty),
             current_state: self.current_state.clone(),
+            last_move: self.last_transition().map_or_else(Vec::new, |t| {
+                t.direction
+                    .iter()
+                    .map(|d| match d {
+                        Direction::Left => -1,
+                        Direction::Right => 1,
+                        Direction::Stay => 0,
+                    })
+                    .collect()
+            }),
         }
     }
 
+    /// returns the tapes of the machine
+    pub fn tapes(&self) -> &[Tape] {
+        &self.tape
+    }
+
     /// input a string to the first tape of machine
     /// # Arguments
     /// * `input` - the input string for first tape
     pub fn input(&mut self, input: &str) {
-        self.tape.push(Tape::new(input));
+        self.input_tapes(&[input])
+            .expect("every machine has at least one tape");
+    }
+
+    /// input one string to each of the first tapes, the other tapes are blank
+    /// # Errors
+    /// * `InputTapeNumberNotMatch` - if there are more strings than tapes,
+    ///   the tapes are not changed then
+    /// # Example
+    /// ```
+    /// # use trm_sim::trm::Machine;
+    /// # let model = r#"{"states": [{"name": "q0", "start": true, "transitions": [
+    /// #     {"cons": "___", "prod": "___", "move": "SSS", "next": "q0"}]}]}"#;
+    /// let mut machine = Machine::new(model, "json").unwrap();
+    /// machine.input_tapes(&["ab", "c"]).unwrap();
+    /// assert_eq!(machine.identifier().tape[1].tape, "c");
+    /// assert_eq!(machine.identifier().tape[2].tape, "_");
+    /// assert!(machine.input_tapes(&["a", "b", "c", "d"]).is_err());
+    /// ```
+    pub fn input_tapes(&mut self, inputs: &[&str]) -> Result<(), MachineRunningError> {
+        if inputs.len() > self.tape_num {
+            return Err(MachineRunningError::InputTapeNumberNotMatch {
+                expected: self.tape_num,
+                found: inputs.len(),
+            });
+        }
+        self.input_end = inputs.first().map_or(0, |s| s.chars().count() as isize);
+        self.tape.extend(inputs.iter().map(|s| Tape::new(s)));
         // insert blank to other tapes
+        for _ in inputs.len()..self.tape_num {
+            self.tape.push(Tape::new(""));
+        }
+        Ok(())
+    }
+
+    /// input a string to the first tape of machine,
+    /// starting at the outside index `start` instead of under the head.
+    /// The heads stay at the outside index 0
+    pub fn input_at(&mut self, start: isize, input: &str) {
+        self.tape.push(Tape::new_at(input, start));
+        self.input_end = start + input.chars().count() as isize;
         for _ in 1..self.tape_num {
             self.tape.push(Tape::new(""));
         }
     }
 
+    /// input one string split by `sep` into the contents of every tape
+    /// # Errors
+    /// * `InputTapeNumberNotMatch` - if the parts are not as many as the tapes,
+    ///   the tapes are not changed then
+    /// # Example
+    /// ```
+    /// # use trm_sim::trm::Machine;
+    /// # let model = r#"{"states": [{"name": "q0", "start": true, "transitions": [
+    /// #     {"cons": "__", "prod": "__", "move": "SS", "next": "q0"}]}]}"#;
+    /// let mut machine = Machine::new(model, "json").unwrap();
+    /// machine.input_delimited("ab|c", '|').unwrap();
+    /// assert_eq!(machine.identifier().tape[1].tape, "c");
+    /// assert!(machine.input_delimited("ab", '|').is_err());
+    /// ```
+    pub fn input_delimited(&mut self, input: &str, sep: char) -> Result<(), MachineRunningError> {
+        let parts = input.split(sep).collect::<Vec<_>>();
+        if parts.len() != self.tape_num {
+            return Err(MachineRunningError::InputTapeNumberNotMatch {
+                expected: self.tape_num,
+                found: parts.len(),
+            });
+        }
+        self.input_end = parts[0].chars().count() as isize;
+        self.tape.extend(parts.into_iter().map(Tape::new));
+        Ok(())
+    }
+
+    /// input the contents of every tape, each with the index of the symbol under its head,
+    /// an index past the end puts the head on a blank after the content.
+    /// Like `input_at`, the heads are at the outside index 0
+    /// # Errors
+    /// * `InputTapeNumberNotMatch` - if the contents are not as many as the tapes,
+    ///   the tapes are not changed then
+    /// # Example
+    /// ```
+    /// # use trm_sim::trm::Machine;
+    /// # let model = r#"{"states": [{"name": "q0", "start": true, "transitions": [
+    /// #     {"cons": "__", "prod": "__", "move": "SS", "next": "q0"}]}]}"#;
+    /// let mut machine = Machine::new(model, "json").unwrap();
+    /// machine.input_with_heads(&[("abc", 1), ("", 0)]).unwrap();
+    /// assert_eq!(machine.tapes()[0].read(), Some('b'));
+    /// assert_eq!(machine.tapes()[0].get(-1), Some('a'));
+    /// ```
+    pub fn input_with_heads(&mut self, tapes: &[(&str, usize)]) -> Result<(), MachineRunningError> {
+        if tapes.len() != self.tape_num {
+            return Err(MachineRunningError::InputTapeNumberNotMatch {
+                expected: self.tape_num,
+                found: tapes.len(),
+            });
+        }
+        let (content, head) = tapes[0];
+        self.input_end = content.chars().count() as isize - head as isize;
+        self.tape.extend(
+            tapes
+                .iter()
+                .map(|(content, head)| Tape::new_at(content, -(*head as isize))),
+        );
+        Ok(())
+    }
+
+    /// get the number of tapes
+    pub fn tape_count(&self) -> usize {
+        self.tape_num
+    }
+
     /// runs the machine for one step
     /// # Errors
-    /// * `NextStateNotFound` - if one transition next state does not exist
+    /// * `StateNotFound` - if the current state does not exist
+    /// * `NextStateNotFound` - if the next state of the taken transition does not exist
+    /// * `LeftOfBoundary` - if the taken transition moves a head left of the start
+    ///   of a one sided tape
     /// # Returns
     /// * `true` - if the machine is in a final state
     /// * `false` - if the machine is not in a final state
     ///
     ///
     pub fn run_once(&mut self) -> Result<bool, MachineRunningError> {
-        // get current state
-        let state = self
-            .states
-            .get(&self.current_state)
-            .ok_or(MachineRunningError::NextStateNotFound)?;
+        // get current state, by id while it is the state the last step went to
+        let state = match self.states.by_id(self.current_id) {
+            Some(state) if state.name == self.current_state => state,
+            _ => self.states.get(&self.current_state).ok_or_else(|| {
+                MachineRunningError::StateNotFound {
+                    state: self.current_state.clone(),
+                }
+            })?,
+        };
 
-        Machine::find_state_transition(state, &self.tape)
-            .map(|t| {
+        let past_input = self.past_input(&self.tape);
+        self.matcher
+            .find(state, &self.tape)
+            .map(|(i, t)| {
                 // get next state
-                let next_state = self
-                    .states
-                    .get(&t.next_state_name)
-                    .ok_or(MachineRunningError::NextStateNotFound)?;
-                // write to tape
-                zip(&t.consume, &t.produce)
-                    .zip(&mut self.tape)
-                    .zip(&t.consume_pattern)
-                    .for_each(|((cp, tape), p)| {
-                        match p.action(*cp.0, *cp.1) {
-                            PatternAction::Keep => {}
-                            PatternAction::Replace(r) => {
-                                if r == self.pattern_config.empty {
-                                    tape.write_blank();
-                                } else {
-                                    tape.write(r);
-                                }
-                            }
-                        }
-                    });
-                // move tape
-                t.direction
-                    .iter()
-                    .zip(&mut self.tape)
-                    .for_each(|(m, tape)| tape.move_to(*m));
-                // set next state
-                self.current_state = next_state.name.clone();
+                let next_state =
+                    Machine::next_state(&self.states, state, t, &self.tape, &self.pattern_config)?;
+                self.read_past_input |= past_input;
+                let fresh = self.frozen.fresh(&self.tape);
+                Machine::apply_transition(t, &mut self.tape, &mut self.stats, &self.pattern_config);
+                self.frozen
+                    .follow(&fresh, &self.tape, self.pattern_config.empty);
+                // set next state, reusing the names of the last step
+                match &mut self.last_transition {
+                    Some((name, index)) => {
+                        name.clone_from(&state.name);
+                        *index = i;
+                    }
+                    last => *last = Some((state.name.clone(), i)),
+                }
+                self.current_state.clone_from(&next_state.name);
+                self.current_id = next_state.id;
+                self.steps += 1;
+                if let Some(choices) = &mut self.choices {
+                    choices.push(i);
+                }
                 Ok(false)
             })
             .unwrap_or(Ok(true))
     }
 
-    /// run until the machine stops
+    /// find the next state of the transition taken from `from` on the tapes,
+    /// checking it does not move left of the start of one sided tapes
+    fn next_state<'a>(
+        states: &'a States,
+        from: &State,
+        t: &Transition,
+        tape: &[Tape],
+        config: &PatternConfig,
+    ) -> Result<&'a State, MachineRunningError> {
+        let left_of_start =
+            zip(&t.direction, tape).position(|(d, tape)| *d == Direction::Left && tape.head() <= 0);
+        if let Some(i) = left_of_start.filter(|_| config.one_sided) {
+            return Err(MachineRunningError::LeftOfBoundary {
+                state: from.name.clone(),
+                tape: i,
+            });
+        }
+        t.next_id.and_then(|id| states.by_id(id)).ok_or_else(|| {
+            MachineRunningError::NextStateNotFound {
+                from: from.name.clone(),
+                missing: t.next_state_name.clone(),
+                heads: tape
+                    .iter()
+                    .map(|t| t.read().unwrap_or(config.empty))
+                    .collect(),
+            }
+        })
+    }
+
+    /// returns the number of steps taken since the last reset
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// zeroes the step count only, the run goes on as it was,
+    /// so `steps` counts the steps of the next phase of the run
+    pub fn reset_counter(&mut self) {
+        self.steps = 0;
+    }
+
+    /// returns the index of the transition taken in its state at each step since the last reset,
+    /// for `replay`. After `execute` of a nondeterministic machine,
+    /// the choices of the accepting branch.
+    /// They are only recorded with `record_choices`, and empty otherwise
+    pub fn choices(&self) -> &[usize] {
+        self.choices.as_deref().unwrap_or_default()
+    }
+
+    /// runs the input again taking the transition of each index in `choices`,
+    /// as recorded by `choices`, which is much smaller than the configurations of a long run.
+    /// A prefix of the choices rebuilds the configuration after as many steps
+    /// # Errors
+    /// * `StateNotFound` - if the current state does not exist
+    /// * `ReplayChoiceNotValid` - if a chosen transition does not exist or does not match the heads
+    /// * `NextStateNotFound` - if the next state of a chosen transition does not exist
+    /// * `LeftOfBoundary` - if a chosen transition moves a head left of the start
+    ///   of a one sided tape
+    /// # Returns
+    /// * `Accepted` or `Rejected` - if the machine halts after the choices
+    /// * `StepLimitReached` - if a transition still matches after the choices
+    pub fn replay(
+        &mut self,
+        input: &str,
+        choices: &[usize],
+    ) -> Result<RunOutcome, MachineRunningError> {
+        self.reset();
+        self.input(input);
+        for (step, &choice) in choices.iter().enumerate() {
+            let state = self.states.get(&self.current_state).ok_or_else(|| {
+                MachineRunningError::StateNotFound {
+                    state: self.current_state.clone(),
+                }
+            })?;
+            let t = state
+                .transitions
+                .get(choice)
+                .filter(|t| self.matcher.matches(t, &self.tape))
+                .ok_or_else(|| MachineRunningError::ReplayChoiceNotValid {
+                    step,
+                    state: state.name.clone(),
+                    choice,
+                })?;
+            let next_state =
+                Machine::next_state(&self.states, state, t, &self.tape, &self.pattern_config)?;
+            self.read_past_input |= self.past_input(&self.tape);
+            let fresh = self.frozen.fresh(&self.tape);
+            Machine::apply_transition(t, &mut self.tape, &mut self.stats, &self.pattern_config);
+            self.frozen
+                .follow(&fresh, &self.tape, self.pattern_config.empty);
+            self.last_transition = Some((state.name.clone(), choice));
+            self.current_state = next_state.name.clone();
+            self.steps += 1;
+            if let Some(choices) = &mut self.choices {
+                choices.push(choice);
+            }
+        }
+        let state = &self.states[&self.current_state];
+        if state
+            .transitions
+            .iter()
+            .any(|t| self.matcher.matches(t, &self.tape))
+        {
+            Ok(RunOutcome::StepLimitReached)
+        } else {
+            Ok(self.halt_outcome())
+        }
+    }
+
+    /// returns the symbol read on the tape at each step of the run, `None` for a blank.
+    /// It is only recorded with `record_stats`, and empty otherwise
+    /// # Example
+    /// ```
+    /// # use trm_sim::trm::Machine;
+    /// # let model = r#"{"states": [{"name": "q0", "start": true, "transitions": [
+    /// #     {"cons": "*", "prod": "*", "move": "R", "next": "q0"}]}]}"#;
+    /// let mut machine = Machine::new(model, "json").unwrap();
+    /// machine.record_stats(true);
+    /// machine.input("ab");
+    /// machine.run().unwrap();
+    /// assert_eq!(machine.head_symbol_trace(0), [Some('a'), Some('b')]);
+    /// ```
+    pub fn head_symbol_trace(&self, tape: usize) -> Vec<Option<char>> {
+        self.stats
+            .as_ref()
+            .and_then(|s| s.tapes.get(tape))
+            .map_or_else(Vec::new, |t| t.reads().to_vec())
+    }
+
+    /// returns the transition taken by the last step,
+    /// `None` before the first step or after a reset
+    pub fn last_transition(&self) -> Option<&Transition> {
+        let (state, i) = self.last_transition.as_ref()?;
+        self.states.get(state)?.transitions.get(*i)
+    }
+
+    /// write the tapes and move the heads as the transition says
+    fn apply_transition(
+        t: &Transition,
+        tape: &mut [Tape],
+        stats: &mut Option<RunStats>,
+        config: &PatternConfig,
+    ) {
+        // tape numbers are checked on construction, zips below must not cut any tape
+        debug_assert_eq!(
+            t.consume.len(),
+            tape.len(),
+            "transition consumes every tape"
+        );
+        debug_assert_eq!(t.direction.len(), tape.len(), "transition moves every tape");
+        // write to tape, escaped symbols are written as is
+        zip(&t.consume, &t.produce)
+            .zip(tape.iter_mut())
+            .zip(zip(&t.consume_pattern, &t.produce_escaped))
+            .enumerate()
+            .for_each(|(i, ((cp, tape), (p, escaped)))| {
+                if let Some(stats) = &mut *stats {
+                    stats.tape_mut(i).record_read(tape.read());
+                }
+                let action = if config.readonly_tapes.contains(&i) {
+                    PatternAction::Keep
+                } else if *escaped {
+                    PatternAction::Replace(*cp.1)
+                } else if config.produce_keeps && config.is_wildcard(*cp.1) {
+                    PatternAction::Keep
+                } else {
+                    p.action(*cp.0, *cp.1)
+                };
+                match action {
+                    PatternAction::Keep => {}
+                    PatternAction::Replace(r) => {
+                        if let Some(stats) = &mut *stats {
+                            stats.tape_mut(i).record_write(tape.head());
+                            stats.record_symbol(r);
+                        }
+                        if r == config.empty && !escaped {
+                            tape.write_blank();
+                        } else {
+                            tape.write(r);
+                        }
+                    }
+                }
+            });
+        // move tape
+        t.direction
+            .iter()
+            .zip(tape.iter_mut())
+            .enumerate()
+            .for_each(|(i, (m, tape))| {
+                tape.move_to(*m);
+                if let (Some(stats), Direction::Left | Direction::Right) = (&mut *stats, m) {
+                    stats.tape_mut(i).record_move(tape.head());
+                }
+            });
+    }
+
+    /// run until the machine stops, returns if it accepts
     /// # Errors
-    /// * `NextStateNotFound` - if one transition next state does not exist
+    /// * the errors of `run_once`, if a step fails
     pub fn run(&mut self) -> Result<bool, MachineRunningError> {
         while !self.run_once()? {}
-        Ok(self.final_states.contains(&self.current_state))
+        Ok(self.accepts_in(&self.current_state, &self.tape, self.read_past_input))
     }
 
-    /// find which transition to use in current pattern config
-    fn find_state_transition<'a>(state: &'a State, tape: &'_ [Tape]) -> Option<&'a Transition> {
-        // filter transitions that match tapes heads
-        state
-            .transitions
+    /// run until the machine stops, for at most `max_steps` steps
+    /// # Errors
+    /// * the errors of `run_once`, if a step fails
+    pub fn run_with_limit(&mut self, max_steps: usize) -> Result<RunOutcome, MachineRunningError> {
+        for _ in 0..max_steps {
+            if self.run_once()? {
+                return Ok(self.halt_outcome());
+            }
+        }
+        // the machine may halt right at the limit
+        if self.is_halted() {
+            Ok(self.halt_outcome())
+        } else {
+            Ok(RunOutcome::StepLimitReached)
+        }
+    }
+
+    /// check if no transition of the current state matches the heads,
+    /// so the machine stops. A halted machine accepts if and only if
+    /// it is in a final state whose accept condition holds,
+    /// a final state with a matching transition keeps running
+    pub fn is_halted(&self) -> bool {
+        self.states
+            .get(&self.current_state)
+            .is_some_and(|state| self.matcher.find(state, &self.tape).is_none())
+    }
+
+    /// explains the verdict of the current configuration in one line, like
+    /// ``accepted: halted in final state `q1` with no matching transition`` or
+    /// ``rejected: halted in non-final state `q2` because no transition matched symbol `2` ...``.
+    /// A machine that has not halted yet is `running`
+    pub fn explain_verdict(&self) -> String {
+        let state = &self.current_state;
+        if !self.is_halted() {
+            return format!("running: state `{state}` has a transition matching the heads");
+        }
+        let empty = self.pattern_config.empty;
+        let symbols = self
+            .tape
             .iter()
-            .find(|t| {
-                t.consume_pattern
+            .map(|t| t.read().unwrap_or(empty))
+            .collect::<Vec<_>>();
+        let heads = match &symbols[..] {
+            [symbol] => format!("symbol `{symbol}` on tape 0"),
+            _ => format!(
+                "symbols `{}` under the heads",
+                symbols.iter().collect::<String>()
+            ),
+        };
+        if !self.final_states.contains(state) {
+            return format!(
+                "rejected: halted in non-final state `{state}` because no transition matched {heads}"
+            );
+        }
+        if self.ran_off_input(&self.tape, self.read_past_input) {
+            return format!("rejected: halted in final state `{state}` past the end of the input");
+        }
+        let accepted =
+            format!("accepted: halted in final state `{state}` with no matching transition");
+        match self.states[state].accept_if {
+            Some(c) if !c.holds(&self.tape, empty) => {
+                format!("rejected: halted in final state `{state}`, but `{c}` does not hold")
+            }
+            Some(c) => format!("{accepted}, and `{c}` holds"),
+            None => accepted,
+        }
+    }
+
+    /// the outcome of a machine that halted,
+    /// `Accepted` in a final state and `Rejected` in any other
+    fn halt_outcome(&self) -> RunOutcome {
+        if self.accepts_in(&self.current_state, &self.tape, self.read_past_input) {
+            RunOutcome::Accepted
+        } else {
+            RunOutcome::Rejected
+        }
+    }
+
+    /// run for at most `max_steps` steps, choosing among all matching transitions
+    /// at random in proportion to their weights, missing weights count as 1.
+    /// The same seed always makes the same choices.
+    /// If all matching transitions have weight 0, the first one is taken.
+    /// # Errors
+    /// * `StateNotFound` - if the current state does not exist
+    /// * `NextStateNotFound` - if the next state of the chosen transition does not exist
+    /// * `LeftOfBoundary` - if the chosen transition moves a head left of the start
+    ///   of a one sided tape
+    pub fn run_probabilistic(
+        &mut self,
+        seed: u64,
+        max_steps: usize,
+    ) -> Result<RunOutcome, MachineRunningError> {
+        let mut rng = SplitMix64::new(seed);
+        for step in 0..=max_steps {
+            let state = self.states.get(&self.current_state).ok_or_else(|| {
+                MachineRunningError::StateNotFound {
+                    state: self.current_state.clone(),
+                }
+            })?;
+            let matched = state
+                .transitions
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| self.matcher.matches(t, &self.tape))
+                .collect::<Vec<_>>();
+            let Some(first) = matched.first() else {
+                return Ok(self.halt_outcome());
+            };
+            if step == max_steps {
+                break;
+            }
+            let total: f64 = matched.iter().map(|(_, t)| t.weight.unwrap_or(1.0)).sum();
+            let mut r = rng.next_f64() * total;
+            let (i, t) = matched
+                .iter()
+                .find(|(_, t)| {
+                    r -= t.weight.unwrap_or(1.0);
+                    r < 0.0
+                })
+                .unwrap_or(first);
+            let next_state =
+                Machine::next_state(&self.states, state, t, &self.tape, &self.pattern_config)?;
+            self.read_past_input |= self.past_input(&self.tape);
+            let fresh = self.frozen.fresh(&self.tape);
+            Machine::apply_transition(t, &mut self.tape, &mut self.stats, &self.pattern_config);
+            self.frozen
+                .follow(&fresh, &self.tape, self.pattern_config.empty);
+            self.last_transition = Some((state.name.clone(), *i));
+            self.current_state = next_state.name.clone();
+            self.steps += 1;
+            if let Some(choices) = &mut self.choices {
+                choices.push(*i);
+            }
+        }
+        Ok(RunOutcome::StepLimitReached)
+    }
+
+    /// run until the machine stops or a configuration repeats.
+    /// With `window` of `None`, the whole configuration is compared,
+    /// so a loop is reported only if the machine really runs forever,
+    /// but long tapes make every step expensive.
+    /// With `window` of `Some(n)`, only the state, the heads and `n` cells each side of
+    /// every head are compared.
+    /// This is cheap and still catches every loop in place,
+    /// but it may report a loop for a machine that would later behave
+    /// differently because of cells outside the window.
+    /// A machine drifting along the tape forever is not caught, like with `None`
+    /// # Errors
+    /// * the errors of `run_once`, if a step fails
+    pub fn run_detect_loop(
+        &mut self,
+        window: Option<usize>,
+    ) -> Result<RunOutcome, MachineRunningError> {
+        let mut seen = HashSet::new();
+        loop {
+            let key = self
+                .tape
+                .iter()
+                .map(|t| match window {
+                    Some(n) => (t.head(), t.window(n)),
+                    None => (t.head(), t.window(t.reach())),
+                })
+                .collect::<Vec<_>>();
+            if !seen.insert((self.current_state.clone(), key)) {
+                return Ok(RunOutcome::LoopDetected);
+            }
+            if self.run_once()? {
+                return Ok(self.halt_outcome());
+            }
+        }
+    }
+
+    /// run for at most `max_steps` steps at about `steps_per_sec` steps per second,
+    /// calling `f` with the identifier after every step.
+    /// The steps are timed against the start, so a slow callback does not slow the pace.
+    /// A rate too high to sleep between steps, infinite, zero or negative
+    /// runs the steps as fast as possible.
+    /// # Errors
+    /// * the errors of `run_once`, if a step fails
+    pub fn run_paced(
+        &mut self,
+        steps_per_sec: f64,
+        max_steps: usize,
+        mut f: impl FnMut(&MachineIdentifier),
+    ) -> Result<RunOutcome, MachineRunningError> {
+        let interval = (steps_per_sec > 0.0)
+            .then(|| Duration::try_from_secs_f64(1.0 / steps_per_sec).ok())
+            .flatten()
+            .filter(|i| !i.is_zero());
+        let start = Instant::now();
+        for step in 0..max_steps {
+            let deadline = interval
+                .and_then(|i| i.checked_mul(u32::try_from(step).ok()?))
+                .and_then(|d| start.checked_add(d));
+            if let Some(wait) = deadline.and_then(|d| d.checked_duration_since(Instant::now())) {
+                std::thread::sleep(wait);
+            }
+            if self.run_once()? {
+                return Ok(self.halt_outcome());
+            }
+            f(&self.identifier());
+        }
+        if self.is_halted() {
+            Ok(self.halt_outcome())
+        } else {
+            Ok(RunOutcome::StepLimitReached)
+        }
+    }
+
+    /// run until the machine stops, or gives up with `BlankScanLoop`
+    /// after more than `max_blank_steps` steps in a row
+    /// that read only blanks and write no symbol.
+    /// Other loops are not detected, the machine may still run forever.
+    /// # Errors
+    /// * the errors of `run_once`, if a step fails
+    pub fn run_guard_blank_scan(
+        &mut self,
+        max_blank_steps: usize,
+    ) -> Result<RunOutcome, MachineRunningError> {
+        let empty = self.pattern_config.empty;
+        let mut blank_steps = 0;
+        loop {
+            let on_blank = self
+                .tape
+                .iter()
+                .all(|t| t.read().is_none_or(|c| c == empty));
+            if self.run_once()? {
+                return Ok(self.halt_outcome());
+            }
+            // on blanks, only a produced symbol other than blank or the consumed one is written
+            let writes = self.last_transition().is_some_and(|t| {
+                zip(&t.consume, &t.produce)
+                    .zip(&t.produce_escaped)
+                    .any(|((c, p), escaped)| *escaped || (*p != empty && p != c))
+            });
+            blank_steps = if on_blank && !writes {
+                blank_steps + 1
+            } else {
+                0
+            };
+            if blank_steps > max_blank_steps {
+                return Ok(RunOutcome::BlankScanLoop);
+            }
+        }
+    }
+
+    /// run for at most `max_steps` steps, stopping as soon as
+    /// the frozen tape at index `tape` contains `needle`, blanks as the empty symbol.
+    /// Returns `true` if the needle appeared, `false` if the machine halted
+    /// or reached the limit first, or the tape does not exist.
+    /// The tape is frozen before the run and after every step,
+    /// so each step costs time linear in the length of the tape.
+    /// # Errors
+    /// * the errors of `run_once`, if a step fails
+    pub fn run_until_tape_contains(
+        &mut self,
+        tape: usize,
+        needle: &str,
+        max_steps: usize,
+    ) -> Result<bool, MachineRunningError> {
+        let contains = |machine: &Machine| {
+            machine
+                .tape
+                .get(tape)
+                .is_some_and(|t| t.freeze(machine.pattern_config.empty).tape.contains(needle))
+        };
+        if contains(self) {
+            return Ok(true);
+        }
+        for _ in 0..max_steps {
+            if self.run_once()? {
+                return Ok(false);
+            }
+            if contains(self) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// resets the machine, inputs the string and runs it for at most `max_steps` steps.
+    /// A deterministic machine just runs, otherwise every matching transition is followed
+    /// breadth first, and the input is accepted if any branch halts in a final state.
+    /// Without an accepting branch, it is rejected once every reachable configuration
+    /// is explored, even if some branches run forever.
+    /// An accepting branch is left in the machine, otherwise the machine is only reset.
+    /// Statistics are not recorded while exploring branches.
+    /// # Errors
+    /// * the errors of `run_once`, if a step of any branch fails
+    pub fn execute(
+        &mut self,
+        input: &str,
+        max_steps: usize,
+    ) -> Result<RunOutcome, MachineRunningError> {
+        self.reset();
+        self.input(input);
+        if self.deterministic {
+            self.run_with_limit(max_steps)
+        } else {
+            self.explore(max_steps)
+        }
+    }
+
+    /// resets the machine and runs the empty input for at most `max_steps` steps,
+    /// a quick check that the machine halts at all. A run failing with an error does not halt.
+    /// A nondeterministic machine halts only if every branch halts within `max_steps` steps,
+    /// so a branch running forever beside an accepting one does not halt
+    /// # Example
+    /// ```
+    /// # use trm_sim::trm::Machine;
+    /// # let model = r#"{"states": [{"name": "q0", "start": true, "transitions": [
+    /// #     {"cons": ".", "prod": ".", "move": "R", "next": "q0"}]}]}"#;
+    /// let mut machine = Machine::new(model, "json").unwrap();
+    /// assert!(!machine.halts_on_empty(100));
+    /// ```
+    pub fn halts_on_empty(&mut self, max_steps: usize) -> bool {
+        if !self.deterministic {
+            self.reset();
+            self.input("");
+            return self.all_branches_halt(max_steps);
+        }
+        matches!(
+            self.execute("", max_steps),
+            Ok(RunOutcome::Accepted | RunOutcome::Rejected)
+        )
+    }
+
+    /// check every branch from the current configuration halts within `max_steps` steps,
+    /// following every matching transition breadth first. A branch failing with an error
+    /// does not halt
+    fn all_branches_halt(&self, max_steps: usize) -> bool {
+        let mut frontier = vec![(self.current_state.clone(), self.tape.clone())];
+        for _ in 0..=max_steps {
+            // branches meeting in the same configuration go on as one
+            let mut seen = HashSet::new();
+            let mut next = vec![];
+            for (name, tape) in &frontier {
+                let Some(state) = self.states.get(name) else {
+                    return false;
+                };
+                let matched = state
+                    .transitions
                     .iter()
-                    .zip(tape)
-                    .all(|(p, t)| p.match_input(t.read()))
-            })
+                    .filter(|t| self.matcher.matches(t, tape));
+                for t in matched {
+                    let Ok(next_state) =
+                        Machine::next_state(&self.states, state, t, tape, &self.pattern_config)
+                    else {
+                        return false;
+                    };
+                    let mut tape = tape.clone();
+                    Machine::apply_transition(t, &mut tape, &mut None, &self.pattern_config);
+                    if seen.insert(configuration(&next_state.name, &tape)) {
+                        next.push((next_state.name.clone(), tape));
+                    }
+                }
+            }
+            if next.is_empty() {
+                return true;
+            }
+            frontier = next;
+        }
+        false
+    }
+
+    /// follows every matching transition breadth first, for `execute`
+    fn explore(&mut self, max_steps: usize) -> Result<RunOutcome, MachineRunningError> {
+        let key = |state: &str, tape: &[Tape], read_past_input: bool| {
+            (configuration(state, tape), read_past_input)
+        };
+        let mut seen = HashSet::from([key(&self.current_state, &self.tape, self.read_past_input)]);
+        let mut frontier = vec![(
+            self.current_state.clone(),
+            self.tape.clone(),
+            self.read_past_input,
+            self.choices.clone(),
+            self.last_transition.clone(),
+        )];
+        for step in 0.. {
+            let mut next = vec![];
+            let mut running = false;
+            for (name, tape, read_past_input, choices, last) in &frontier {
+                let state =
+                    self.states
+                        .get(name)
+                        .ok_or_else(|| MachineRunningError::StateNotFound {
+                            state: name.clone(),
+                        })?;
+                let mut matched = state
+                    .transitions
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, t)| self.matcher.matches(t, tape))
+                    .peekable();
+                if matched.peek().is_none() {
+                    if self.accepts_in(name, tape, *read_past_input) {
+                        self.current_state = name.clone();
+                        self.tape = tape.clone();
+                        self.read_past_input = *read_past_input;
+                        self.steps += step;
+                        self.choices.clone_from(choices);
+                        self.last_transition.clone_from(last);
+                        return Ok(RunOutcome::Accepted);
+                    }
+                    continue;
+                }
+                running = true;
+                if step == max_steps {
+                    continue;
+                }
+                for (i, t) in matched {
+                    let next_state =
+                        Machine::next_state(&self.states, state, t, tape, &self.pattern_config)?;
+                    let read_past_input = *read_past_input || self.past_input(tape);
+                    let mut tape = tape.clone();
+                    Machine::apply_transition(t, &mut tape, &mut None, &self.pattern_config);
+                    if seen.insert(key(&next_state.name, &tape, read_past_input)) {
+                        // branches only carry their choices while they are recorded
+                        let mut choices = choices.clone();
+                        if let Some(choices) = &mut choices {
+                            choices.push(i);
+                        }
+                        let last = Some((name.clone(), i));
+                        next.push((
+                            next_state.name.clone(),
+                            tape,
+                            read_past_input,
+                            choices,
+                            last,
+                        ));
+                    }
+                }
+            }
+            if running && step == max_steps {
+                break;
+            }
+            if next.is_empty() {
+                return Ok(RunOutcome::Rejected);
+            }
+            frontier = next;
+        }
+        Ok(RunOutcome::StepLimitReached)
+    }
+
+    /// trim the blank ends of every tape,
+    /// the identifier of the machine does not change
+    pub fn trim_tapes(&mut self) {
+        for tape in &mut self.tape {
+            tape.trim();
+        }
     }
 
-    /// check if the machine is in a final state
+    /// get the config for pattern matching
+    pub fn pattern_config(&self) -> &PatternConfig {
+        &self.pattern_config
+    }
+
+    /// get the tests declared in the model
+    pub fn tests(&self) -> &[ModelTest] {
+        &self.tests
+    }
+
+    /// get the names of the final states, sorted
+    pub fn final_states(&self) -> Vec<&str> {
+        let mut names = self
+            .final_states
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// returns a short human readable summary of the states,
+    /// with the states and final states sorted by name
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::Machine;
+    /// let model = r#"
+    /// [[state]]
+    /// name = "q1"
+    /// final = true
+    /// [[state]]
+    /// name = "q0"
+    /// start = true
+    /// final = true
+    /// "#;
+    /// let machine = Machine::new(model, "toml").unwrap();
+    /// assert_eq!(
+    ///     machine.describe(),
+    ///     "States: `q0`, `q1`\nStart: `q0`\nFinal: `q0`, `q1`\n"
+    /// );
+    /// ```
+    pub fn describe(&self) -> String {
+        let mut names = self.states.keys().map(String::as_str).collect::<Vec<_>>();
+        names.sort();
+        format!(
+            "States: `{}`\nStart: `{}`\nFinal: `{}`\n",
+            names.join("`, `"),
+            self.start_state,
+            self.final_states().join("`, `")
+        )
+    }
+
+    /// get the number of states
+    pub fn state_count(&self) -> usize {
+        self.states.len()
+    }
+
+    /// get the number of transitions of all states
+    pub fn transition_count(&self) -> usize {
+        self.states.values().map(|s| s.transitions.len()).sum()
+    }
+
+    /// get the transitions leaving the state in their order,
+    /// empty if the state does not exist
+    pub fn outgoing(&self, state: &str) -> &[Transition] {
+        self.states.get(state).map_or(&[], |s| &s.transitions)
+    }
+
+    /// get the transitions entering the state with the states they leave,
+    /// sorted by the state left, then in the order of its transitions
+    pub fn incoming(&self, state: &str) -> Vec<(&str, &Transition)> {
+        let mut incoming = self
+            .states
+            .values()
+            .flat_map(|s| s.transitions.iter().map(|t| (s.name.as_str(), t)))
+            .filter(|(_, t)| t.next_state_name == state)
+            .collect::<Vec<_>>();
+        incoming.sort_by_key(|(from, _)| *from);
+        incoming
+    }
+
+    /// check if the machine is in a final state,
+    /// the accept condition of the state is not checked
     pub fn is_final(&self) -> bool {
         self.final_states.contains(&self.current_state)
     }
 
-    /// get the model of the machine
+    /// check if halting in the state with the tapes accepts,
+    /// the state is final and its accept condition holds.
+    fn accepts_in(&self, state: &str, tape: &[Tape], read_past_input: bool) -> bool {
+        !self.ran_off_input(tape, read_past_input)
+            && self.final_states.contains(state)
+            && self.states[state]
+                .accept_if
+                .is_none_or(|c| c.holds(tape, self.pattern_config.empty))
+    }
+
+    /// check if `reject_end_of_input` is set and the first head is past the last input symbol,
+    /// but no transition was taken there
+    fn ran_off_input(&self, tape: &[Tape], read_past_input: bool) -> bool {
+        self.pattern_config.reject_end_of_input && !read_past_input && self.past_input(tape)
+    }
+
+    /// check if the first head is past the last input symbol
+    fn past_input(&self, tape: &[Tape]) -> bool {
+        tape.first().is_some_and(|t| t.head() >= self.input_end)
+    }
+
+    /// extracts a finite automaton recognizing the same language,
+    /// if the machine is one tape, always moves right and never writes.
+    /// Returns `None` if the machine really uses the tape.
+    pub fn as_dfa(&self) -> Option<Dfa> {
+        let scans_only = self.tape_num == 1
+            && self.states.values().flat_map(|s| &s.transitions).all(|t| {
+                t.consume == t.produce
+                    && t.consume_escaped == t.produce_escaped
+                    && matches!(t.direction[..], [Direction::Right])
+                    && self.states.contains_key(&t.next_state_name)
+            })
+            && self.states.values().all(|s| s.accept_if.is_none())
+            && !self.pattern_config.reject_end_of_input;
+        if !scans_only {
+            return None;
+        }
+        let transitions = self
+            .states
+            .values()
+            .map(|s| {
+                let trans = s
+                    .transitions
+                    .iter()
+                    .filter_map(|t| {
+                        let pattern = t.consume_pattern.first()?.clone();
+                        Some((pattern, t.next_state_name.clone()))
+                    })
+                    .collect();
+                (s.name.clone(), trans)
+            })
+            .collect();
+        Some(Dfa {
+            start: self.start_state.clone(),
+            final_states: self.final_states.clone(),
+            transitions,
+        })
+    }
+
+    /// statically check the machine for suspicious constructs,
+    /// the machine is valid anyway, but may not do what the author meant
+    pub fn validate(&self) -> Vec<ValidationWarning> {
+        let mut warnings = vec![];
+        let mut live_finals = self
+            .final_states
+            .iter()
+            .filter(|s| {
+                self.states
+                    .get(s)
+                    .is_some_and(|s| !s.transitions.is_empty())
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if !live_finals.is_empty() {
+            live_finals.sort();
+            warnings.push(ValidationWarning::FinalStateHasTransitions(live_finals));
+        }
+        let transitions = self
+            .states
+            .values()
+            .flat_map(|s| &s.transitions)
+            .collect::<Vec<_>>();
+        // literal symbols written to each tape, wildcards keep what they read
+        let written = transitions.iter().flat_map(|t| {
+            zip(&t.produce, &t.produce_escaped)
+                .enumerate()
+                .filter(|(_, (c, escaped))| **escaped || !self.pattern_config.is_meta(**c))
+                .map(|(i, (c, _))| (i, *c))
+        });
+        let mut write_only = written
+            .filter(|(i, c)| {
+                !transitions
+                    .iter()
+                    .any(|t| t.consume_pattern[*i].match_input(Some(*c)))
+            })
+            .map(|(_, c)| c)
+            .collect::<Vec<_>>();
+        if !write_only.is_empty() {
+            write_only.sort();
+            write_only.dedup();
+            warnings.push(ValidationWarning::WriteOnlySymbols(write_only));
+        }
+        if self.pattern_config.one_sided {
+            let mut left = self.moves_left_of_start();
+            if !left.is_empty() {
+                left.sort();
+                warnings.push(ValidationWarning::MovesLeftOfBoundary(left));
+            }
+        }
+        let mut on_blank = self.some_wildcard_on_blank();
+        if !on_blank.is_empty() {
+            on_blank.sort();
+            on_blank.dedup();
+            warnings.push(ValidationWarning::SomeWildcardOnBlank(on_blank));
+        }
+        warnings
+    }
+
+    /// the transitions consuming `some_wildcard` on a tape surely blank in their state,
+    /// as every transition into the state writes blank there and stays
+    fn some_wildcard_on_blank(&self) -> Vec<String> {
+        let config = &self.pattern_config;
+        let Some(some) = config.some_wildcard else {
+            return vec![];
+        };
+        let mut found = vec![];
+        for state in self.states.values() {
+            let incoming = self.incoming(&state.name);
+            if state.name == self.start_state || incoming.is_empty() {
+                continue;
+            }
+            let blank = (0..self.tape_num).filter(|&i| {
+                incoming.iter().all(|(_, t)| {
+                    t.direction[i] == Direction::Stay
+                        && !t.produce_escaped[i]
+                        && t.produce[i] == config.empty
+                })
+            });
+            for i in blank {
+                found.extend(
+                    state
+                        .transitions
+                        .iter()
+                        .filter(|t| !t.consume_escaped[i] && t.consume[i] == some)
+                        .map(|t| format!("{}: {}", state.name, t.describe())),
+                );
+            }
+        }
+        found
+    }
+
+    /// the transitions moving left on a tape whose head is surely at the start,
+    /// as it only stayed there since the start state
+    fn moves_left_of_start(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut queue = vec![(self.start_state.clone(), vec![true; self.tape_num])];
+        let mut left = vec![];
+        while let Some((name, at_start)) = queue.pop() {
+            if !seen.insert((name.clone(), at_start.clone())) {
+                continue;
+            }
+            let Some(state) = self.states.get(&name) else {
+                continue;
+            };
+            for t in &state.transitions {
+                let moves_left = zip(&t.direction, &at_start)
+                    .any(|(d, at_start)| *at_start && *d == Direction::Left);
+                let describe = format!("{name}: {}", t.describe());
+                if moves_left && !left.contains(&describe) {
+                    left.push(describe);
+                }
+                let at_start = zip(&t.direction, &at_start)
+                    .map(|(d, at_start)| *at_start && *d == Direction::Stay)
+                    .collect::<Vec<_>>();
+                if at_start.contains(&true) {
+                    queue.push((t.next_state_name.clone(), at_start));
+                }
+            }
+        }
+        left
+    }
+
+    /// check if at most one transition of every state can match any heads,
+    /// so the order of the transitions never decides which one is taken
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// check if both machines have the same start and final states, config,
+    /// and transitions of every state, ignoring the tapes and the state of a run
+    /// and the order the states were declared in.
+    /// The transitions of a state are compared in order only if one of the machines
+    /// is not deterministic, since the order decides between overlapping transitions then
+    /// # Example
+    /// ```
+    /// # use trm_sim::trm::Machine;
+    /// let a = Machine::new(r#"{"states": [{"name": "q0", "start": true},
+    ///     {"name": "q1", "final": true}]}"#, "json").unwrap();
+    /// let b = Machine::new(r#"{"states": [{"name": "q1", "final": true},
+    ///     {"name": "q0", "start": true}]}"#, "json").unwrap();
+    /// assert!(a.structural_eq(&b));
+    /// assert!(!a.structural_eq(&Machine::default()));
+    /// ```
+    pub fn structural_eq(&self, other: &Machine) -> bool {
+        let config = |m: &Machine| serde_json::to_value(&m.pattern_config).ok();
+        let ordered = !(self.deterministic && other.deterministic);
+        // folded transitions are compared as the transitions they were folded from
+        let transitions = |m: &Machine, s: &State| {
+            let mut transitions = s
+                .transitions
+                .iter()
+                .flat_map(Transition::sources)
+                .map(|t| t.to_serde(&m.pattern_config))
+                .map(|t| serde_json::to_string(&t).unwrap_or_default())
+                .collect::<Vec<_>>();
+            if !ordered {
+                transitions.sort();
+            }
+            transitions
+        };
+        self.start_state == other.start_state
+            && self.final_states == other.final_states
+            && self.states.len() == other.states.len()
+            && config(self) == config(other)
+            && self.states.iter().all(|(name, state)| {
+                other.states.get(name).is_some_and(|o| {
+                    state.accept_if == o.accept_if
+                        && transitions(self, state) == transitions(other, o)
+                })
+            })
+    }
+
+    /// get the model of the machine, with the states sorted by name
+    /// so serializing it gives the same output every time
     pub fn model(&self) -> MachineModel {
-        let states = self.states.values().map(|s| s.to_serde()).collect();
+        let mut states = self.states.values().collect::<Vec<_>>();
+        states.sort_by(|a, b| a.name.cmp(&b.name));
+        let states = states
+            .into_iter()
+            .map(|s| s.to_serde(&self.pattern_config))
+            .collect();
         MachineModel {
+            version: Some(MODEL_VERSION),
+            include: vec![],
             state: states,
-            pattern_config: self.pattern_config,
+            template: vec![],
+            pattern_config: self.pattern_config.clone(),
+            tests: self.tests.clone(),
         }
     }
 }
 
+/// a state with the head and the written cells around it of every tape
+type Configuration = (String, Vec<(isize, Vec<Option<char>>)>);
+
+/// the configuration of the state and tapes, the same for the same state and tapes
+fn configuration(state: &str, tape: &[Tape]) -> Configuration {
+    let cells = tape
+        .iter()
+        .map(|t| (t.head(), t.window(t.reach())))
+        .collect();
+    (state.to_string(), cells)
+}
+
+impl Default for Machine {
+    /// a machine of one state `q0`, both start and final, without transitions,
+    /// so it accepts any input in zero steps
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::Machine;
+    /// let mut machine = Machine::default();
+    /// machine.input("any input");
+    /// assert!(machine.run().unwrap());
+    /// ```
+    fn default() -> Self {
+        Machine::new(
+            "[[state]]\nname = \"q0\"\nstart = true\nfinal = true\n",
+            "toml",
+        )
+        .expect("the default machine is valid")
+    }
+}
+
+impl MachineIdentifier {
+    /// returns a multi-line human readable view,
+    /// the state and each tape with the head cell in brackets
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::{FrozenTape, MachineIdentifier};
+    /// let id = MachineIdentifier {
+    ///     current_state: "q0".to_string(),
+    ///     tape: vec![FrozenTape { tape: "01".to_string(), head: 1, range: 0..2 }],
+    ///     last_move: vec![],
+    /// };
+    /// assert_eq!(id.to_string_pretty(), "State: q0\nTape 0: 0[1]\nHead 0: 1\nRange (0..2)\n");
+    /// ```
+    pub fn to_string_pretty(&self) -> String {
+        let mut s = format!("State: {}\n", self.current_state);
+        for (i, tape) in self.tape.iter().enumerate() {
+            s.push_str(&format!("Tape {i}: {tape}\n"));
+            s.push_str(&format!("Head {i}: {}\n", tape.head));
+            s.push_str(&format!(
+                "Range ({}..{})\n",
+                tape.range.start, tape.range.end
+            ));
+        }
+        s
+    }
+}
+
 impl MachineModel {
+    /// creates a new machine model of the current version in code
+    pub fn new(state: Vec<StateSerde>, pattern_config: PatternConfig) -> Self {
+        Self {
+            version: None,
+            include: vec![],
+            state,
+            template: vec![],
+            pattern_config,
+            tests: vec![],
+        }
+    }
+
     /// creates a new machine model from a string,
     /// with given model format.
     /// # Arguments
     /// * `model` - the model of the machine
-    /// * `fmt` - the format of the model
+    /// * `fmt` - the format of the model, `json`, `toml`, or `yaml` (also `yml`)
     /// # Errors
     /// * `SyntaxError` - if the model is not valid
     pub fn from_str(model: &str, fmt: &str) -> Result<Self, SyntaxError> {
-        let model = match fmt {
-            "json" => serde_json::from_str(model).map_err(|e| SyntaxError {
-                error_type: SyntaxErrorType::SyntaxNotValid(e.to_string()),
-                message: "json deserializer failed.".to_string(),
-            })?,
-            "toml" => toml::from_str(model).map_err(|e| SyntaxError {
-                error_type: SyntaxErrorType::SyntaxNotValid(e.to_string()),
-                message: "toml deserializer failed.".to_string(),
-            })?,
+        Self::parse(model, fmt, false)
+    }
+
+    /// creates a new machine model from a string like `from_str`,
+    /// but fails on any field the model does not know.
+    /// # Arguments
+    /// * `model` - the model of the machine
+    /// * `fmt` - the format of the model
+    /// # Errors
+    /// * `SyntaxError` - if the model is not valid or has unknown fields
+    pub fn from_str_strict(model: &str, fmt: &str) -> Result<Self, SyntaxError> {
+        Self::parse(model, fmt, true)
+    }
+
+    /// creates a new machine model from a parsed json value,
+    /// without serializing it to a string again
+    /// # Errors
+    /// * `SyntaxError` - if the model is not valid
+    pub fn from_json_value(value: serde_json::Value) -> Result<Self, SyntaxError> {
+        let model: Self =
+            serde_json::from_value(value).map_err(|e| Self::deserializer_error("json", e))?;
+        model.check_version()?;
+        Ok(model)
+    }
+
+    /// creates a new machine model from a parsed toml value,
+    /// without serializing it to a string again
+    /// # Errors
+    /// * `SyntaxError` - if the model is not valid
+    pub fn from_toml_value(value: toml::Value) -> Result<Self, SyntaxError> {
+        let model: Self = value
+            .try_into()
+            .map_err(|e| Self::deserializer_error("toml", e))?;
+        model.check_version()?;
+        Ok(model)
+    }
+
+    /// serializes the model in the given format, `json`, `toml`, or `yaml` (also `yml`)
+    /// # Errors
+    /// * `SyntaxError` - if the format is not supported or serializing fails
+    pub fn to_text(&self, fmt: &str) -> Result<String, SyntaxError> {
+        let error = |e: String| {
+            let message = format!("{fmt} serializer failed.");
+            SyntaxError::new(SyntaxErrorType::SyntaxNotValid(e), message)
+        };
+        match fmt {
+            "json" => serde_json::to_string_pretty(self).map_err(|e| error(e.to_string())),
+            "toml" => toml::to_string(self).map_err(|e| error(e.to_string())),
+            "yaml" | "yml" => serde_yaml::to_string(self).map_err(|e| error(e.to_string())),
+            _ => Err(SyntaxError::new(
+                SyntaxErrorType::FormatNotProvided,
+                format!("not provided format: {fmt}"),
+            )),
+        }
+    }
+
+    /// deserialize the model, collecting the paths of ignored fields.
+    /// A model nested as the only key `machine` of the document is unwrapped
+    fn parse(model: &str, fmt: &str, strict: bool) -> Result<Self, SyntaxError> {
+        let mut unknown = Vec::new();
+        let mut on_ignored = |path: serde_ignored::Path| unknown.push(path.to_string());
+        let model: Self = match fmt {
+            "json" => match Self::nested_json(model) {
+                Some(inner) => serde_ignored::deserialize(inner, &mut on_ignored)
+                    .map_err(|e| Self::deserializer_error("json", e))?,
+                None => {
+                    let mut de = serde_json::Deserializer::from_str(model);
+                    serde_ignored::deserialize(&mut de, &mut on_ignored)
+                        .and_then(|m| de.end().map(|_| m))
+                        .map_err(|e| Self::deserializer_error("json", e))?
+                }
+            },
+            "toml" => match Self::nested_toml(model) {
+                Some(inner) => serde_ignored::deserialize(inner, &mut on_ignored)
+                    .map_err(|e| Self::deserializer_error("toml", e))?,
+                None => {
+                    let de = toml::Deserializer::new(model);
+                    serde_ignored::deserialize(de, &mut on_ignored)
+                        .map_err(|e| Self::deserializer_error("toml", e))?
+                }
+            },
+            "yaml" | "yml" => match Self::nested_yaml(model) {
+                Some(inner) => serde_ignored::deserialize(inner, &mut on_ignored)
+                    .map_err(|e| Self::deserializer_error("yaml", e))?,
+                None => {
+                    let de = serde_yaml::Deserializer::from_str(model);
+                    serde_ignored::deserialize(de, &mut on_ignored)
+                        .map_err(|e| Self::deserializer_error("yaml", e))?
+                }
+            },
             _ => {
-                return Err(SyntaxError {
-                    error_type: SyntaxErrorType::FormatNotProvided,
-                    message: format!("not provided format: {fmt}"),
-                })
+                return Err(SyntaxError::new(
+                    SyntaxErrorType::FormatNotProvided,
+                    format!("not provided format: {fmt}"),
+                ))
             }
         };
 
+        if strict && !unknown.is_empty() {
+            return Err(SyntaxError::new(
+                SyntaxErrorType::UnknownField(unknown.join(", ")),
+                format!("unknown fields in model: {}", unknown.join(", ")),
+            ));
+        }
+        model.check_version()?;
         Ok(model)
     }
+
+    /// the model nested under `machine` in a json document, if it is the only key.
+    /// Documents that do not parse are left to the normal path to report the error
+    fn nested_json(model: &str) -> Option<serde_json::Value> {
+        if !model.contains("machine") {
+            return None;
+        }
+        let serde_json::Value::Object(mut document) = serde_json::from_str(model).ok()? else {
+            return None;
+        };
+        let inner = document.remove("machine").filter(|_| document.is_empty())?;
+        inner.is_object().then_some(inner)
+    }
+
+    /// the model nested under a `[machine]` table in a toml document, if it is the only key
+    fn nested_toml(model: &str) -> Option<toml::Value> {
+        if !model.contains("machine") {
+            return None;
+        }
+        let mut document = model.parse::<toml::Table>().ok()?;
+        let inner = document.remove("machine").filter(|_| document.is_empty())?;
+        inner.is_table().then_some(inner)
+    }
+
+    /// the model nested under `machine` in a yaml document, if it is the only key
+    fn nested_yaml(model: &str) -> Option<serde_yaml::Value> {
+        if !model.contains("machine") {
+            return None;
+        }
+        let serde_yaml::Value::Mapping(mut document) = serde_yaml::from_str(model).ok()? else {
+            return None;
+        };
+        let inner = document.remove("machine").filter(|_| document.is_empty())?;
+        inner.is_mapping().then_some(inner)
+    }
+
+    /// wrap a deserializer error, keeping it as the error source
+    fn deserializer_error<E>(fmt: &str, e: E) -> SyntaxError
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        SyntaxError::new(
+            SyntaxErrorType::SyntaxNotValid(e.to_string()),
+            format!("{fmt} deserializer failed."),
+        )
+        .with_source(e)
+    }
+
+    /// name unnamed states by position, and resolve transitions to states by position
+    /// # Errors
+    /// * `StateNameDuplicated` - if two states have the same name,
+    ///   like a state named `s1` and the unnamed state at position 1
+    pub(crate) fn resolve_names(&mut self) -> Result<(), SyntaxError> {
+        let names = self
+            .state
+            .iter()
+            .enumerate()
+            .map(|(i, s)| match s.name() {
+                "" => auto_name(i),
+                name => name.to_string(),
+            })
+            .collect::<Vec<_>>();
+        let mut seen = HashMap::new();
+        for (i, name) in names.iter().enumerate() {
+            let Some(first) = seen.insert(name, i) else {
+                continue;
+            };
+            let unnamed = [first, i]
+                .into_iter()
+                .find(|&j| self.state[j].name().is_empty());
+            let reason = match unnamed {
+                Some(j) => format!(", the unnamed state at position {j} is named `{name}`"),
+                None => String::new(),
+            };
+            return Err(SyntaxError::new(
+                SyntaxErrorType::StateNameDuplicated,
+                format!("state `{name}` is defined twice{reason}"),
+            ));
+        }
+        for (i, state) in self.state.iter_mut().enumerate() {
+            state.resolve_names(i, &names)?;
+        }
+        Ok(())
+    }
+
+    /// check the model is not larger than the limits
+    fn check_limits(&self, limits: &ModelLimits) -> Result<(), SyntaxError> {
+        // every transition counts as often as its groups expand it
+        let transitions = self
+            .state
+            .iter()
+            .flat_map(|s| s.transitions())
+            .map(|t| t.expanded_size(&self.pattern_config));
+        let sizes = [
+            ("states", self.state.len(), limits.max_states),
+            (
+                "transitions",
+                transitions
+                    .clone()
+                    .fold(0, |sum, (n, _)| sum.saturating_add(n)),
+                limits.max_transitions,
+            ),
+            (
+                "consumed symbols",
+                transitions.fold(0, |sum, (_, len)| sum.saturating_add(len)),
+                limits.max_consume_len,
+            ),
+        ];
+        match sizes.into_iter().find(|(_, size, max)| size > max) {
+            Some((what, size, max)) => Err(SyntaxError::new(
+                SyntaxErrorType::ModelLimitExceeded,
+                format!("model has {size} {what}, but at most {max} are allowed"),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// check the model version is supported by this crate
+    fn check_version(&self) -> Result<(), SyntaxError> {
+        match self.version {
+            Some(v) if v > MODEL_VERSION => Err(SyntaxError::new(
+                SyntaxErrorType::VersionNotSupported(v),
+                format!(
+                    "model version {v} is not supported, the newest supported version is {MODEL_VERSION}"
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
 }
@@ -4,14 +4,58 @@ use std::fmt::{Display, Formatter};
 /// Machine running error
 #[derive(Debug, Clone)]
 pub enum MachineRunningError {
-    /// the transition next state is not found
-    NextStateNotFound,
+    /// the current state of the machine is not found
+    StateNotFound { state: String },
+    /// the next state of the taken transition is not found,
+    /// `heads` are the symbols read by the transition, blanks as the empty symbol
+    NextStateNotFound {
+        from: String,
+        missing: String,
+        heads: Vec<char>,
+    },
+    /// the input has a different number of tapes than the machine
+    InputTapeNumberNotMatch { expected: usize, found: usize },
+    /// the transition taken would move the head of the tape left of the start of a one sided tape
+    LeftOfBoundary { state: String, tape: usize },
+    /// the transition chosen at the step of a replay does not exist or does not match
+    ReplayChoiceNotValid {
+        step: usize,
+        state: String,
+        choice: usize,
+    },
 }
 
 impl Display for MachineRunningError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            MachineRunningError::NextStateNotFound => write!(f, "Next state not found."),
+            MachineRunningError::StateNotFound { state } => {
+                write!(f, "Current state `{state}` not found.")
+            }
+            MachineRunningError::NextStateNotFound {
+                from,
+                missing,
+                heads,
+            } => write!(
+                f,
+                "Next state `{missing}` not found, taking a transition of `{from}` reading `{}`.",
+                heads.iter().collect::<String>()
+            ),
+            MachineRunningError::InputTapeNumberNotMatch { expected, found } => write!(
+                f,
+                "Input has {found} tapes, but the machine has {expected} tapes."
+            ),
+            MachineRunningError::LeftOfBoundary { state, tape } => write!(
+                f,
+                "A transition of `{state}` moves the head of tape {tape} left of its start."
+            ),
+            MachineRunningError::ReplayChoiceNotValid {
+                step,
+                state,
+                choice,
+            } => write!(
+                f,
+                "Replay chooses transition {choice} of `{state}` at step {step}, which does not match."
+            ),
         }
     }
 }
@@ -0,0 +1,184 @@
+//! This module contains how the heads are matched against the transitions of a state.
+
+use crate::trm::{Pattern, State, States, Tape, Transition};
+use std::collections::HashSet;
+use std::iter::zip;
+
+/// the widest range of symbols a jump table is built for
+const MAX_TABLE_SYMBOLS: u32 = 256;
+
+/// how the heads are matched against the transitions,
+/// every way finds the same transition
+#[derive(Debug, Clone)]
+pub(crate) enum Matcher {
+    /// through the consume patterns
+    Patterns,
+    /// by comparing the consumed chars directly,
+    /// no transition consumes a meta char.
+    /// The consume patterns are still built, writes and overlap checks use them
+    Literal,
+    /// by looking the transition up by state and symbol,
+    /// for literal machines with one tape
+    Table(JumpTable),
+}
+
+/// check if some heads are matched by both transitions.
+/// Besides blank and the literals of each tape,
+/// a symbol neither of them consumes stands for the ones only wildcards match
+/// Symbols of the transitions folded into them count as their literals.
+/// An opaque pattern is taken to overlap another opaque one or a wildcard
+pub(crate) fn overlaps(a: &Transition, b: &Transition) -> bool {
+    let sources = a.sources().iter().chain(b.sources()).collect::<Vec<_>>();
+    let literals = sources
+        .iter()
+        .flat_map(|t| &t.consume)
+        .collect::<HashSet<_>>();
+    let fresh = ('\u{e000}'..).find(|c| !literals.contains(c));
+    zip(&a.consume_pattern, &b.consume_pattern)
+        .enumerate()
+        .all(|(i, (pa, pb))| {
+            let mut candidates = vec![None, fresh];
+            candidates.extend(sources.iter().map(|t| Some(t.consume[i])));
+            let opaque = |p: &dyn Pattern, q: &dyn Pattern| {
+                p.is_opaque() && (q.is_opaque() || q.match_input(fresh))
+            };
+            candidates
+                .iter()
+                .any(|c| pa.match_input(*c) && pb.match_input(*c))
+                || opaque(pa.as_ref(), pb.as_ref())
+                || opaque(pb.as_ref(), pa.as_ref())
+        })
+}
+
+/// the first matching transition of every state for every symbol,
+/// states are indexed by their id and symbols from the smallest consumed one
+#[derive(Debug, Clone)]
+pub(crate) struct JumpTable {
+    /// the smallest consumed symbol
+    first: u32,
+    /// the transition index per symbol, of each state by id
+    rows: Vec<Vec<Option<usize>>>,
+}
+
+impl Matcher {
+    /// choose the fastest way to match the transitions of the states
+    pub(crate) fn new(states: &States, literal_only: bool, tape_num: usize) -> Self {
+        if !literal_only {
+            return Matcher::Patterns;
+        }
+        if tape_num != 1 {
+            return Matcher::Literal;
+        }
+        JumpTable::new(states).map_or(Matcher::Literal, Matcher::Table)
+    }
+
+    /// find the first transition of the state matching the heads
+    pub(crate) fn find<'a>(
+        &self,
+        state: &'a State,
+        tape: &[Tape],
+    ) -> Option<(usize, &'a Transition)> {
+        match self {
+            Matcher::Table(table) => {
+                let i = table.get(state.id, tape.first()?.read()?)?;
+                Some((i, &state.transitions[i]))
+            }
+            _ => state
+                .transitions
+                .iter()
+                .enumerate()
+                .find(|(_, t)| self.matches(t, tape)),
+        }
+    }
+
+    /// check if the transition matches the heads
+    pub(crate) fn matches(&self, t: &Transition, tape: &[Tape]) -> bool {
+        match self {
+            Matcher::Patterns => t
+                .consume_pattern
+                .iter()
+                .zip(tape)
+                .all(|(p, t)| p.match_input(t.read())),
+            Matcher::Literal | Matcher::Table(_) => {
+                zip(&t.consume, tape).all(|(c, t)| t.read() == Some(*c))
+            }
+        }
+    }
+}
+
+impl JumpTable {
+    /// build the table, `None` if the consumed symbols are too far apart
+    fn new(states: &States) -> Option<Self> {
+        let symbols = || {
+            states
+                .values()
+                .flat_map(|s| &s.transitions)
+                .filter_map(|t| t.consume.first())
+                .map(|c| *c as u32)
+        };
+        let first = symbols().min().unwrap_or(0);
+        let width = symbols().max().map_or(0, |last| last - first + 1);
+        if width > MAX_TABLE_SYMBOLS {
+            return None;
+        }
+        // the states are ordered by id
+        let rows = states
+            .values()
+            .map(|s| {
+                let mut row = vec![None; width as usize];
+                // in reverse, so the first declared transition wins
+                for (i, t) in s.transitions.iter().enumerate().rev() {
+                    row[(t.consume[0] as u32 - first) as usize] = Some(i);
+                }
+                row
+            })
+            .collect();
+        Some(Self { first, rows })
+    }
+
+    /// the transition index of the state with the id for the symbol
+    fn get(&self, state: usize, symbol: char) -> Option<usize> {
+        let index = (symbol as u32).checked_sub(self.first)?;
+        *self.rows.get(state)?.get(index as usize)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trm::Machine;
+
+    /// the matcher chosen for a one state machine with the given consumed symbols
+    fn matcher_of(conses: &[&str]) -> Matcher {
+        let trans = conses
+            .iter()
+            .map(|c| {
+                let prod = c.replace(['*', '.'], "0");
+                let mv = "R".repeat(c.chars().count());
+                format!("[[state.trans]]\ncons = \"{c}\"\nprod = \"{prod}\"\nmove = \"{mv}\"\nnext = \"q0\"\n")
+            })
+            .collect::<String>();
+        let model = format!("[[state]]\nname = \"q0\"\nstart = true\n{trans}");
+        Machine::new(&model, "toml").unwrap().matcher
+    }
+
+    #[test]
+    fn test_matcher_choice() {
+        assert!(matches!(matcher_of(&["0", "1"]), Matcher::Table(_)));
+        assert!(matches!(matcher_of(&["0", "*"]), Matcher::Patterns));
+        assert!(matches!(matcher_of(&["01", "10"]), Matcher::Literal));
+        assert!(matches!(matcher_of(&["0", "\u{4e00}"]), Matcher::Literal));
+    }
+
+    #[test]
+    fn test_jump_table_first_wins() {
+        let Matcher::Table(table) = matcher_of(&["b", "a", "b"]) else {
+            panic!("expected a jump table");
+        };
+        assert_eq!(table.get(0, 'a'), Some(1));
+        assert_eq!(table.get(0, 'b'), Some(0));
+        assert_eq!(table.get(0, 'c'), None);
+        assert_eq!(table.get(0, '0'), None);
+        assert_eq!(table.get(1, 'a'), None);
+    }
+}
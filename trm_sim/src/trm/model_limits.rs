@@ -0,0 +1,30 @@
+//! This module contains the size limits of a model from untrusted input.
+
+/// the largest model `Machine::new_with_limits` accepts,
+/// every limit is unbounded by default
+/// # Example
+/// ```
+/// use trm_sim::trm::{Machine, ModelLimits};
+/// let model = r#"{"states": [{"name": "q0", "start": true}, {"name": "q1"}]}"#;
+/// let limits = ModelLimits { max_states: 1, ..Default::default() };
+/// assert!(Machine::new_with_limits(model, "json", limits).is_err());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ModelLimits {
+    /// the most states
+    pub max_states: usize,
+    /// the most transitions of all states
+    pub max_transitions: usize,
+    /// the most symbols consumed by all transitions together
+    pub max_consume_len: usize,
+}
+
+impl Default for ModelLimits {
+    fn default() -> Self {
+        Self {
+            max_states: usize::MAX,
+            max_transitions: usize::MAX,
+            max_consume_len: usize::MAX,
+        }
+    }
+}
@@ -1,6 +1,8 @@
 //! deal char pattern like wildcards and nullable
 
+use crate::trm::{SyntaxError, SyntaxErrorType};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone)]
 pub enum PatternAction {
@@ -18,12 +20,45 @@ impl PatternAction {
     }
 }
 
-pub trait Pattern {
+pub trait Pattern: PatternClone {
     fn match_input(&self, input: Option<char>) -> bool;
 
     fn action(&self, cons: char, prod: char) -> PatternAction;
+
+    /// if the pattern may match symbols that are neither consumed literally nor only
+    /// matched by wildcards, so checking those symbols cannot tell if patterns overlap
+    fn is_opaque(&self) -> bool {
+        false
+    }
+}
+
+/// helper trait to clone boxed patterns,
+/// implemented for every pattern that is `Clone`
+pub trait PatternClone {
+    fn clone_box(&self) -> Box<dyn Pattern>;
 }
 
+impl<T: Pattern + Clone + 'static> PatternClone for T {
+    fn clone_box(&self) -> Box<dyn Pattern> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Pattern> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// matches only its own symbol, never a blank cell
+/// # Example
+/// ```
+/// use trm_sim::trm::{CharPattern, Pattern};
+/// let pattern = CharPattern { pattern: '0' };
+/// assert!(pattern.match_input(Some('0')));
+/// assert!(!pattern.match_input(Some('1')));
+/// assert!(!pattern.match_input(None));
+/// ```
 #[derive(Debug, Clone)]
 pub struct CharPattern {
     pub pattern: char,
@@ -40,6 +75,13 @@ impl Pattern for CharPattern {
     }
 }
 
+/// matches only a blank cell
+/// # Example
+/// ```
+/// use trm_sim::trm::{EmptyPattern, Pattern};
+/// assert!(EmptyPattern.match_input(None));
+/// assert!(!EmptyPattern.match_input(Some('0')));
+/// ```
 #[derive(Debug, Clone)]
 pub struct EmptyPattern;
 
@@ -54,6 +96,13 @@ impl Pattern for EmptyPattern {
     }
 }
 
+/// the `some` wildcard, `*` by default, matches any symbol but never a blank cell
+/// # Example
+/// ```
+/// use trm_sim::trm::{Pattern, SomeWildcardPattern};
+/// assert!(SomeWildcardPattern.match_input(Some('0')));
+/// assert!(!SomeWildcardPattern.match_input(None));
+/// ```
 #[derive(Debug, Clone)]
 pub struct SomeWildcardPattern;
 
@@ -68,6 +117,80 @@ impl Pattern for SomeWildcardPattern {
     }
 }
 
+/// matches any of its symbols, never a blank cell.
+/// It keeps the symbol read, or writes the produced one
+/// # Example
+/// ```
+/// use trm_sim::trm::{ClassPattern, Pattern};
+/// let pattern = ClassPattern { symbols: vec!['0', '1'], keep: true };
+/// assert!(pattern.match_input(Some('1')));
+/// assert!(!pattern.match_input(Some('2')));
+/// assert!(!pattern.match_input(None));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClassPattern {
+    pub symbols: Vec<char>,
+    pub keep: bool,
+}
+
+impl Pattern for ClassPattern {
+    fn match_input(&self, input: Option<char>) -> bool {
+        input.is_some_and(|c| self.symbols.contains(&c))
+    }
+
+    fn action(&self, _cons: char, prod: char) -> PatternAction {
+        PatternAction::new(self.keep, prod)
+    }
+}
+
+/// matches a symbol the whole regex matches, never a blank cell.
+/// It keeps the symbol read, or writes the produced one
+/// # Example
+/// ```
+/// use trm_sim::trm::{Pattern, RegexPattern};
+/// let pattern = RegexPattern::new(r"\d").unwrap();
+/// assert!(pattern.match_input(Some('7')));
+/// assert!(!pattern.match_input(Some('a')));
+/// assert!(!pattern.match_input(None));
+/// ```
+#[cfg(feature = "regex")]
+#[derive(Debug, Clone)]
+pub struct RegexPattern {
+    pub regex: regex::Regex,
+}
+
+#[cfg(feature = "regex")]
+impl RegexPattern {
+    /// compile the regex to match a whole symbol
+    pub fn new(regex: &str) -> Result<Self, regex::Error> {
+        let regex = regex::Regex::new(&format!("^(?:{regex})$"))?;
+        Ok(Self { regex })
+    }
+}
+
+#[cfg(feature = "regex")]
+impl Pattern for RegexPattern {
+    fn match_input(&self, input: Option<char>) -> bool {
+        input.is_some_and(|c| self.regex.is_match(c.encode_utf8(&mut [0; 4])))
+    }
+
+    fn action(&self, cons: char, prod: char) -> PatternAction {
+        // keep if cons == prod
+        PatternAction::new(cons == prod, prod)
+    }
+
+    fn is_opaque(&self) -> bool {
+        true
+    }
+}
+
+/// the `any` wildcard, `.` by default, matches any symbol and a blank cell
+/// # Example
+/// ```
+/// use trm_sim::trm::{AnyPattern, Pattern};
+/// assert!(AnyPattern.match_input(Some('0')));
+/// assert!(AnyPattern.match_input(None));
+/// ```
 #[derive(Debug, Clone)]
 pub struct AnyPattern;
 
@@ -82,35 +205,202 @@ impl Pattern for AnyPattern {
     }
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+/// the `config` table of a model,
+/// mostly about how symbols are parsed into patterns,
+/// missing fields take their default value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PatternConfig {
+    /// the blank symbol
     #[serde(rename = "empty")]
     pub empty: char,
-    #[serde(rename = "some")]
-    pub some_wildcard: char,
-    pub any: char,
+    /// matches any non-blank symbol, `None` (`""` in a model) disables it
+    #[serde(rename = "some", with = "meta_char")]
+    pub some_wildcard: Option<char>,
+    /// matches any symbol, including blank, `None` (`""` in a model) disables it
+    #[serde(with = "meta_char")]
+    pub any: Option<char>,
+    /// if no state is marked as start,
+    /// use the only state without incoming transitions
+    pub infer_start: bool,
+    /// sort the transitions of each state by `cons` then `next`,
+    /// so the order of declaration does not matter.
+    /// It changes which transition wins if several match
+    pub sort_transitions: bool,
+    /// let transitions consume fewer tapes than the machine has,
+    /// the trailing tapes then match `any`, keep their symbol and stay.
+    /// It needs `any` enabled, and a transition still has to consume
+    /// as many symbols as it produces and moves
+    pub pad_tapes: bool,
+    /// reject a machine halting as soon as the first head passes the last input symbol,
+    /// even in a final state, as a recognizer that ran off its input.
+    /// A machine that takes a transition there works on the blanks, and is judged as usual
+    pub reject_end_of_input: bool,
+    /// a `some` or `any` wildcard in `prod` keeps the cell of its tape
+    /// whatever was consumed there, like `prod = "*_"` keeping the first tape.
+    /// Otherwise it is only valid under the same wildcard in `cons`
+    pub produce_keeps: bool,
+    /// the tapes end left of the outside index 0, moving a head left of it is an error
+    pub one_sided: bool,
+    /// the tapes transitions may read but not write, every transition must keep their cells.
+    /// Indexes past the last tape are ignored
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub readonly_tapes: Vec<usize>,
+    /// the tape transitions may use as a stack with `push` and `pop`, its head is on the top,
+    /// a blank when the stack is empty. Popping erases the top and moves left,
+    /// pushing moves right and writes the symbol, taking one more step if not popping too
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack_tape: Option<usize>,
+    /// read a `cons` between slashes like `/\d/` as a regex matching the symbol on one tape,
+    /// a wildcard `prod` keeps the symbol. It needs `any` or `some` enabled,
+    /// and the `regex` feature
+    pub regex_cons: bool,
+    /// named groups of symbols, `{name}` in `cons` matches any symbol of the group
+    /// and a transition is built for each of them, a literal `{` then needs escaping.
+    /// The same `{name}` on the same tape in `prod` writes back the symbol matched
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, String>,
+    /// the `move` of transitions omitting it, a single direction moves every tape.
+    /// `None` by default, so every transition must have a `move`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_move: Option<String>,
+    /// a symbol after it in `cons` or `prod` is a literal, even if it is a meta char,
+    /// `None` (`""` in a model) by default. It must not be a meta char itself
+    #[serde(with = "meta_char")]
+    pub escape: Option<char>,
+    /// the symbols meaning stay in `move`, case insensitive, `S.-` by default.
+    /// They are only read in `move`, so `.` does not clash with the `any` pattern in `cons`.
+    /// `L` and `R` always move, even if listed here
+    pub stay: String,
 }
 
 impl Default for PatternConfig {
     fn default() -> Self {
         Self {
             empty: '_',
-            some_wildcard: '*',
-            any: '.',
+            some_wildcard: Some('*'),
+            any: Some('.'),
+            infer_start: false,
+            sort_transitions: false,
+            pad_tapes: false,
+            reject_end_of_input: false,
+            produce_keeps: false,
+            one_sided: false,
+            readonly_tapes: vec![],
+            stack_tape: None,
+            regex_cons: false,
+            groups: BTreeMap::new(),
+            default_move: None,
+            escape: None,
+            stay: "S.-".to_string(),
         }
     }
 }
 
 impl PatternConfig {
+    /// check if the symbol is parsed as anything but a literal
+    pub fn is_meta(&self, c: char) -> bool {
+        c == self.empty || Some(c) == self.some_wildcard || Some(c) == self.any
+    }
+
+    /// check the escape char is none of the meta chars,
+    /// an escape char escaping itself could not be told from the meta char
+    /// # Errors
+    /// * `ConfigEscapeNotValid` - if the escape char is `empty`, `some` or `any`
+    pub(crate) fn check_escape(&self) -> Result<(), SyntaxError> {
+        let Some(e) = self.escape else {
+            return Ok(());
+        };
+        let clash = if e == self.empty {
+            "empty"
+        } else if Some(e) == self.some_wildcard {
+            "some"
+        } else if Some(e) == self.any {
+            "any"
+        } else {
+            return Ok(());
+        };
+        Err(SyntaxError::new(
+            SyntaxErrorType::ConfigEscapeNotValid,
+            format!("config `escape` is `{e}`, which is already the `{clash}` symbol"),
+        ))
+    }
+
+    /// check if the symbol is the `some` or the `any` wildcard
+    pub fn is_wildcard(&self, c: char) -> bool {
+        Some(c) == self.some_wildcard || Some(c) == self.any
+    }
+
     pub fn parse(&self, pattern: &[char]) -> Vec<Box<dyn Pattern>> {
+        self.parse_escaped(pattern, &vec![false; pattern.len()])
+    }
+
+    /// parse symbols into patterns, escaped symbols are always literals
+    pub fn parse_escaped(&self, pattern: &[char], escaped: &[bool]) -> Vec<Box<dyn Pattern>> {
         pattern
             .iter()
-            .map(|c| match *c {
-                c if c == self.empty => Box::new(EmptyPattern) as Box<dyn Pattern>,
-                c if c == self.some_wildcard => Box::new(SomeWildcardPattern),
-                c if c == self.any => Box::new(AnyPattern),
+            .zip(escaped)
+            .map(|(c, escaped)| match *c {
+                c if *escaped => Box::new(CharPattern { pattern: c }) as Box<dyn Pattern>,
+                c if c == self.empty => Box::new(EmptyPattern),
+                c if Some(c) == self.some_wildcard => Box::new(SomeWildcardPattern),
+                c if Some(c) == self.any => Box::new(AnyPattern),
                 c => Box::new(CharPattern { pattern: c }),
             })
             .collect()
     }
+
+    /// split `cons` or `prod` into symbols and if each of them is escaped.
+    /// Returns `None` if the string ends with a lone escape char
+    pub fn split_escapes(&self, s: &str) -> Option<(Vec<char>, Vec<bool>)> {
+        let mut symbols = vec![];
+        let mut escaped = vec![];
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if Some(c) == self.escape {
+                symbols.push(chars.next()?);
+                escaped.push(true);
+            } else {
+                symbols.push(c);
+                escaped.push(false);
+            }
+        }
+        Some((symbols, escaped))
+    }
+
+    /// join symbols back into a string, the reverse of `split_escapes`
+    pub fn join_escapes(&self, symbols: &[char], escaped: &[bool]) -> String {
+        let mut s = String::new();
+        for (c, escaped) in symbols.iter().zip(escaped) {
+            if let (true, Some(e)) = (*escaped, self.escape) {
+                s.push(e);
+            }
+            s.push(*c);
+        }
+        s
+    }
+}
+
+/// serde of a meta char that can be disabled,
+/// an empty string or `null` means disabled
+mod meta_char {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(c: &Option<char>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&c.map(String::from).unwrap_or_default())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<char>, D::Error> {
+        let s = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (c, None) => Ok(c),
+            _ => Err(D::Error::custom(format!(
+                "expected a single char or \"\", found `{s}`"
+            ))),
+        }
+    }
 }
@@ -0,0 +1,61 @@
+//! This module contains the generation of random machines,
+//! as fixtures for tools working on machines.
+
+use crate::trm::rng::SplitMix64;
+use crate::trm::{Machine, MachineModel, PatternConfig, StateSerde, TransitionSerde};
+
+impl Machine {
+    /// generates a random one tape machine with `n_states` states, at least one,
+    /// named `q0` to `q{n_states - 1}`. `q0` is the start state and at least one state is final.
+    /// Every state reads each symbol of the alphabet or blank with at most one
+    /// transition, writing a symbol of the alphabet and moving to a random state,
+    /// so the machine is deterministic and every next state exists.
+    /// Symbols the default config reads as patterns are left out of the alphabet.
+    /// The same seed always gives the same machine.
+    pub fn random(seed: u64, n_states: usize, alphabet: &[char]) -> Machine {
+        let config = PatternConfig::default();
+        let mut rng = SplitMix64::new(seed);
+        let mut symbols = alphabet
+            .iter()
+            .copied()
+            .filter(|c| !config.is_meta(*c))
+            .collect::<Vec<_>>();
+        symbols.sort();
+        symbols.dedup();
+        let n_states = n_states.max(1);
+        let name = |i: usize| format!("q{i}");
+        // the state always made final, any state may be final besides it
+        let final_state = rng.next_below(n_states);
+        let reads = symbols
+            .iter()
+            .copied()
+            .chain([config.empty])
+            .collect::<Vec<_>>();
+        let states = (0..n_states)
+            .map(|i| {
+                let trans = reads
+                    .iter()
+                    .filter_map(|c| {
+                        // about a quarter of the symbols are left without a transition
+                        if rng.next_below(4) == 0 {
+                            return None;
+                        }
+                        let prod = if symbols.is_empty() {
+                            *c
+                        } else {
+                            symbols[rng.next_below(symbols.len())]
+                        };
+                        let direction = ["L", "R", "S"][rng.next_below(3)];
+                        let next = name(rng.next_below(n_states));
+                        let (cons, prod) = (c.to_string(), prod.to_string());
+                        Some(TransitionSerde::new(&cons, &prod, direction, &next))
+                    })
+                    .collect();
+                let is_final = i == final_state || rng.next_below(3) == 0;
+                StateSerde::new(&name(i), i == 0, is_final, trans)
+            })
+            .collect();
+        let model = MachineModel::new(states, config);
+        Machine::from_model(model).expect("random machines are valid")
+    }
+}
@@ -0,0 +1,17 @@
+//! This module contains the outcome of a guarded run.
+
+/// how a run ended, for runs that can stop before the machine halts
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// the machine halted in a final state
+    Accepted,
+    /// the machine halted in a non final state
+    Rejected,
+    /// a configuration repeated, the machine would run forever
+    LoopDetected,
+    /// the machine did not halt within the step limit
+    StepLimitReached,
+    /// the machine kept reading blanks without writing anything,
+    /// like a scan that missed the end of its input
+    BlankScanLoop,
+}
@@ -0,0 +1,72 @@
+//! This module contains the statistics collected while running a machine.
+
+use std::collections::{HashMap, HashSet};
+
+/// statistics of one tape during a run
+#[derive(Debug, Clone, Default)]
+pub struct TapeStats {
+    /// the number of head moves, `Stay` is not counted
+    pub head_moves: usize,
+    /// the leftmost outside index the head has reached
+    pub max_left: isize,
+    /// the rightmost outside index the head has reached
+    pub max_right: isize,
+    /// outside indexes of the cells written
+    written: HashSet<isize>,
+    /// the symbol under the head at each step, `None` for a blank
+    reads: Vec<Option<char>>,
+}
+
+/// statistics of one run, one entry per tape
+#[derive(Debug, Clone, Default)]
+pub struct RunStats {
+    /// the statistics of each tape
+    pub tapes: Vec<TapeStats>,
+    /// how often each symbol was written on any tape,
+    /// blanks written count as the empty symbol
+    pub writes_per_symbol: HashMap<char, usize>,
+}
+
+impl TapeStats {
+    /// returns the number of distinct cells written
+    pub fn cells_written(&self) -> usize {
+        self.written.len()
+    }
+
+    /// returns the symbol under the head at each step, `None` for a blank
+    pub fn reads(&self) -> &[Option<char>] {
+        &self.reads
+    }
+
+    /// record the symbol under the head when a step is taken
+    pub(crate) fn record_read(&mut self, symbol: Option<char>) {
+        self.reads.push(symbol);
+    }
+
+    /// record a write at the given outside index
+    pub(crate) fn record_write(&mut self, index: isize) {
+        self.written.insert(index);
+    }
+
+    /// record a move, `head` is the outside index after moving
+    pub(crate) fn record_move(&mut self, head: isize) {
+        self.head_moves += 1;
+        self.max_left = self.max_left.min(head);
+        self.max_right = self.max_right.max(head);
+    }
+}
+
+impl RunStats {
+    /// get the statistics of one tape, creating them if needed
+    pub(crate) fn tape_mut(&mut self, index: usize) -> &mut TapeStats {
+        if self.tapes.len() <= index {
+            self.tapes.resize_with(index + 1, TapeStats::default);
+        }
+        &mut self.tapes[index]
+    }
+
+    /// record a write of the symbol on any tape
+    pub(crate) fn record_symbol(&mut self, symbol: char) {
+        *self.writes_per_symbol.entry(symbol).or_default() += 1;
+    }
+}
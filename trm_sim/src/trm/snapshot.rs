@@ -0,0 +1,110 @@
+//! This module contains saving a machine in the middle of a run,
+//! to continue it later from its model, even in another process.
+
+use crate::trm::{Machine, SyntaxError, SyntaxErrorType, Tape};
+use serde::{Deserialize, Serialize};
+
+/// everything a run changes in a machine, saved by `Machine::snapshot`.
+/// The model is not part of it, `Machine::resume` takes the model again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// the current state
+    pub state: String,
+    /// the tapes, blank cells included
+    pub tapes: Vec<Tape>,
+    /// the steps taken since the last reset
+    pub steps: usize,
+    /// the index of the transition taken at each step, for `Machine::replay`,
+    /// `None` if the choices were not recorded
+    #[serde(default)]
+    pub choices: Option<Vec<usize>>,
+    /// the state and index of the transition taken by the last step
+    pub last_transition: Option<(String, usize)>,
+    /// the outside index right after the last input symbol on the first tape
+    pub input_end: isize,
+    /// if a transition was taken with the first head past the last input symbol
+    pub read_past_input: bool,
+    /// if the machine of the model is deterministic, to tell models apart
+    pub deterministic: bool,
+}
+
+impl Machine {
+    /// saves the current state, the tapes and the step count as json,
+    /// `resume` continues the run from it. Run statistics are not saved
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::Machine;
+    /// # let model = r#"{"states": [{"name": "q0", "start": true, "final": true, "transitions": [
+    /// #     {"cons": "0", "prod": "1", "move": "R", "next": "q0"}]}]}"#;
+    /// let mut machine = Machine::new(model, "json").unwrap();
+    /// machine.input("000");
+    /// machine.run_once().unwrap();
+    /// let snapshot = machine.snapshot();
+    ///
+    /// let mut resumed = Machine::resume(model, "json", &snapshot).unwrap();
+    /// assert_eq!(resumed.steps(), 1);
+    /// assert!(resumed.run().unwrap());
+    /// assert_eq!(resumed.identifier().tape[0].tape, "111_");
+    /// ```
+    pub fn snapshot(&self) -> String {
+        let snapshot = Snapshot {
+            state: self.current_state.clone(),
+            tapes: self.tape.clone(),
+            steps: self.steps,
+            choices: self.choices.clone(),
+            last_transition: self.last_transition.clone(),
+            input_end: self.input_end,
+            read_past_input: self.read_past_input,
+            deterministic: self.deterministic,
+        };
+        serde_json::to_string(&snapshot).expect("a snapshot is always serializable")
+    }
+
+    /// builds the machine of the model like `new`,
+    /// and continues the run saved in the snapshot
+    /// # Errors
+    /// * `SyntaxError` - if the model or the snapshot is not valid, like a head outside its tape,
+    ///   or `SnapshotNotMatch` if the snapshot was taken from a machine of another model
+    pub fn resume(model: &str, fmt: &str, snapshot: &str) -> Result<Self, SyntaxError> {
+        let mut machine = Machine::new(model, fmt)?;
+        let snapshot: Snapshot = serde_json::from_str(snapshot).map_err(|e| {
+            SyntaxError::new(
+                SyntaxErrorType::SyntaxNotValid(e.to_string()),
+                "snapshot deserializer failed.".to_string(),
+            )
+            .with_source(e)
+        })?;
+        if let Some(i) = snapshot.tapes.iter().position(|t| !t.is_valid()) {
+            return Err(SyntaxError::new(
+                SyntaxErrorType::SyntaxNotValid(format!(
+                    "the head of tape {i} is outside the tape"
+                )),
+                "snapshot is not valid.".to_string(),
+            ));
+        }
+        let last_transition_found = snapshot.last_transition.as_ref().is_none_or(|(state, i)| {
+            machine
+                .states
+                .get(state)
+                .is_some_and(|s| *i < s.transitions.len())
+        });
+        let fits = machine.states.contains_key(&snapshot.state)
+            && snapshot.tapes.len() == machine.tape_count()
+            && snapshot.deterministic == machine.deterministic
+            && last_transition_found;
+        if !fits {
+            return Err(SyntaxError::new(
+                SyntaxErrorType::SnapshotNotMatch,
+                "the snapshot was not taken from a machine of this model".to_string(),
+            ));
+        }
+        machine.current_state = snapshot.state;
+        machine.tape = snapshot.tapes;
+        machine.steps = snapshot.steps;
+        machine.choices = snapshot.choices;
+        machine.last_transition = snapshot.last_transition;
+        machine.input_end = snapshot.input_end;
+        machine.read_past_input = snapshot.read_past_input;
+        Ok(machine)
+    }
+}
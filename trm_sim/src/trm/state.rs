@@ -1,11 +1,14 @@
 //! This module is for definition of turing machine state and transition structs.
 
-use crate::trm::syntax_error::SyntaxError;
+use crate::trm::syntax_error::{SyntaxError, SyntaxErrorType};
 use crate::trm::transition::{Transition, TransitionSerde};
-use crate::trm::PatternConfig;
+use crate::trm::{AcceptCondition, PatternConfig};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Index;
 
 /// a turing machine state
+#[derive(Clone)]
 pub struct State {
     /// the name of the state
     pub name: String,
@@ -13,21 +16,45 @@ pub struct State {
     pub is_start: bool,
     /// is this state a final state
     pub is_final: bool,
+    /// what else must hold to accept when halting in this final state
+    pub accept_if: Option<AcceptCondition>,
+    /// the blank symbol of the transitions instead of the config `empty`, if any.
+    /// The transitions are stored with the config `empty`, only reading them uses this
+    pub blank: Option<char>,
     /// the transitions of the state
     pub transitions: Vec<Transition>,
+    /// the index of the state in its machine, given when the machine is built
+    pub(crate) id: usize,
+}
+
+/// the states of a machine, numbered by name so a step finds them by id
+#[derive(Clone)]
+pub(crate) struct States {
+    /// the states, indexed by their id
+    states: Vec<State>,
+    /// the id of every state name
+    ids: HashMap<String, usize>,
 }
 
 /// a helper struct for serde state
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StateSerde {
-    /// the name of the state
+    /// the name of the state, an unnamed state is named by its position like `s0`
+    #[serde(default)]
     name: String,
     /// is this state the start state
-    #[serde(default, alias = "start")]
+    #[serde(default, alias = "start", deserialize_with = "flag::deserialize")]
     is_start: bool,
     /// is this state a final state
-    #[serde(default, alias = "final")]
+    #[serde(default, alias = "final", deserialize_with = "flag::deserialize")]
     is_final: bool,
+    /// what else must hold to accept when halting in this final state
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    accept_if: Option<String>,
+    /// the blank symbol of the transitions instead of the config `empty`.
+    /// The config `empty` is an ordinary symbol then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    blank: Option<char>,
 
     /// the transitions of the state
     #[serde(default, alias = "transitions")]
@@ -36,44 +63,258 @@ pub struct StateSerde {
 
 impl State {
     /// create new state from StateSerde
-    pub fn try_from_serde(state: StateSerde, config: PatternConfig) -> Result<Self, SyntaxError> {
+    pub fn try_from_serde(state: StateSerde, config: &PatternConfig) -> Result<Self, SyntaxError> {
         state.into_state(config)
     }
 
     /// get StateSerde
-    pub fn to_serde(&self) -> StateSerde {
-        StateSerde::from_state(self)
+    pub fn to_serde(&self, config: &PatternConfig) -> StateSerde {
+        StateSerde::from_state(self, config)
+    }
+}
+
+impl States {
+    /// number the states by name, and link every transition to the id of its next state
+    pub(crate) fn new(states: HashMap<String, State>) -> Self {
+        let mut states = states.into_values().collect::<Vec<_>>();
+        states.sort_by(|a, b| a.name.cmp(&b.name));
+        let ids = states
+            .iter()
+            .enumerate()
+            .map(|(id, s)| (s.name.clone(), id))
+            .collect::<HashMap<_, _>>();
+        for (id, state) in states.iter_mut().enumerate() {
+            state.id = id;
+            for t in &mut state.transitions {
+                t.next_id = ids.get(&t.next_state_name).copied();
+            }
+        }
+        Self { states, ids }
+    }
+
+    /// the state with the name
+    pub(crate) fn get(&self, name: &str) -> Option<&State> {
+        self.states.get(*self.ids.get(name)?)
+    }
+
+    /// the state with the id
+    pub(crate) fn by_id(&self, id: usize) -> Option<&State> {
+        self.states.get(id)
+    }
+
+    /// check if a state has the name
+    pub(crate) fn contains_key(&self, name: &str) -> bool {
+        self.ids.contains_key(name)
+    }
+
+    /// the names of the states, ordered by id
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &String> {
+        self.states.iter().map(|s| &s.name)
+    }
+
+    /// the states, ordered by id
+    pub(crate) fn values(&self) -> std::slice::Iter<'_, State> {
+        self.states.iter()
+    }
+
+    /// the states to change in place, the names and next states must stay the same
+    pub(crate) fn values_mut(&mut self) -> std::slice::IterMut<'_, State> {
+        self.states.iter_mut()
+    }
+
+    /// the names and states, ordered by id
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&String, &State)> {
+        self.states.iter().map(|s| (&s.name, s))
+    }
+
+    /// the number of states
+    pub(crate) fn len(&self) -> usize {
+        self.states.len()
+    }
+}
+
+impl Index<&str> for States {
+    type Output = State;
+
+    fn index(&self, name: &str) -> &State {
+        self.get(name).expect("no state with the name")
     }
 }
 
 impl StateSerde {
+    /// create a serde state in code
+    pub fn new(name: &str, is_start: bool, is_final: bool, trans: Vec<TransitionSerde>) -> Self {
+        Self {
+            name: name.to_string(),
+            is_start,
+            is_final,
+            accept_if: None,
+            blank: None,
+            trans,
+        }
+    }
+
+    /// name the state at position `i` in the model, and refer to the next states
+    /// of its transitions by name, `names` are the names of all states in order
+    pub(crate) fn resolve_names(&mut self, i: usize, names: &[String]) -> Result<(), SyntaxError> {
+        self.name.clone_from(&names[i]);
+        self.trans
+            .iter_mut()
+            .try_for_each(|t| t.resolve_next(names, &self.name))
+    }
+
+    /// compile the `push` and `pop` of the transitions into ordinary transitions,
+    /// returns the states added to write pushed symbols
+    pub(crate) fn expand_stack(
+        &mut self,
+        config: &PatternConfig,
+    ) -> Result<Vec<StateSerde>, SyntaxError> {
+        let mut added = vec![];
+        for t in &mut self.trans {
+            if let Some((name, push)) = t.expand_stack(config)? {
+                added.push(StateSerde::new(&name, false, false, vec![push]));
+            }
+        }
+        Ok(added)
+    }
+
+    /// the name of the state in the model, empty if unnamed
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// the transitions of the state
+    pub(crate) fn transitions(&self) -> &[TransitionSerde] {
+        &self.trans
+    }
+
     /// into state with syntax check
-    pub fn into_state(self, config: PatternConfig) -> Result<State, SyntaxError> {
+    pub fn into_state(self, config: &PatternConfig) -> Result<State, SyntaxError> {
+        let local = self.local_config(config)?;
         let transitions = self
             .trans
             .into_iter()
-            .map(|t| t.into_transition(config))
+            .map(|t| t.expand_groups(&local))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .map(|t| {
+                let mut t = t.into_transition(&local)?;
+                if local.empty != config.empty {
+                    t.rebase_blank(local.empty, config.empty);
+                    // the patterns follow the rebased symbols, read with the machine config
+                    if t.consume_regex.is_none() {
+                        t.consume_pattern = config.parse_escaped(&t.consume, &t.consume_escaped);
+                    }
+                }
+                Ok(t)
+            })
             .collect::<Result<_, _>>()?;
+        let accept_if = self
+            .accept_if
+            .map(|c| {
+                AcceptCondition::parse(&c).ok_or_else(|| {
+                    SyntaxError::new(
+                        SyntaxErrorType::StateAcceptConditionNotValid,
+                        format!(
+                            "state `{}` accepts if `{c}`, which is not one of \
+                             `tapeN_empty` or `headN_at_origin`",
+                            self.name
+                        ),
+                    )
+                })
+            })
+            .transpose()?;
 
         Ok(State {
             name: self.name,
             is_start: self.is_start,
             is_final: self.is_final,
+            accept_if,
+            blank: self.blank,
             transitions,
+            id: 0,
+        })
+    }
+
+    /// the config to read the transitions with, using the blank of the state
+    fn local_config(&self, config: &PatternConfig) -> Result<PatternConfig, SyntaxError> {
+        let Some(blank) = self.blank else {
+            return Ok(config.clone());
+        };
+        if config.is_wildcard(blank) || Some(blank) == config.escape {
+            return Err(SyntaxError::new(
+                SyntaxErrorType::StateBlankNotValid,
+                format!(
+                    "state `{}` uses `{blank}` as blank, which is a meta char of the config",
+                    self.name
+                ),
+            ));
+        }
+        Ok(PatternConfig {
+            empty: blank,
+            ..config.clone()
         })
     }
 
     /// create serializable state from state reference
-    pub fn from_state(state: &State) -> Self {
+    pub fn from_state(state: &State, config: &PatternConfig) -> Self {
         Self {
             name: state.name.clone(),
             is_start: state.is_start,
             is_final: state.is_final,
+            accept_if: state.accept_if.map(|c| c.to_string()),
+            blank: state.blank,
+            // folded transitions are written as the transitions they were folded from
             trans: state
                 .transitions
                 .iter()
-                .map(TransitionSerde::from_transition)
+                .flat_map(Transition::sources)
+                .map(|t| match state.blank {
+                    Some(blank) => {
+                        let mut t = t.clone();
+                        t.rebase_blank(config.empty, blank);
+                        TransitionSerde::from_transition(&t, config)
+                    }
+                    None => TransitionSerde::from_transition(t, config),
+                })
                 .collect(),
         }
     }
 }
+
+/// a boolean flag of a state, the strings `"true"` and `"false"` are read as booleans too,
+/// so a quoted flag is not mistaken for another type
+mod flag {
+    use serde::de::{Error, Visitor};
+    use serde::Deserializer;
+    use std::fmt::Formatter;
+
+    struct FlagVisitor;
+
+    impl Visitor<'_> for FlagVisitor {
+        type Value = bool;
+
+        fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+            write!(f, "a boolean `true` or `false`")
+        }
+
+        fn visit_bool<E: Error>(self, v: bool) -> Result<bool, E> {
+            Ok(v)
+        }
+
+        fn visit_str<E: Error>(self, v: &str) -> Result<bool, E> {
+            match v.trim().to_lowercase().as_str() {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(E::custom(format!(
+                    "expected a boolean `true` or `false`, found the string `{v}`"
+                ))),
+            }
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        deserializer.deserialize_any(FlagVisitor)
+    }
+}
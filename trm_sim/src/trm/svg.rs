@@ -0,0 +1,89 @@
+//! This module contains the export of a run as svg frames.
+
+use crate::trm::{Machine, Tape};
+use std::fmt::Write;
+
+/// the width and height of a tape cell
+const CELL: isize = 30;
+
+/// escape text for svg
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+impl Machine {
+    /// export running the input for at most `max_steps` steps as one svg per configuration,
+    /// the first before any step. Each shows the current state and the cells of every tape,
+    /// the head cell highlighted. All frames show the same cells, so they line up in slides.
+    /// The run uses a copy of the machine and stops at the first error
+    pub fn to_svg_frames(&self, input: &str, max_steps: usize) -> Vec<String> {
+        let mut machine = self.clone();
+        machine.reset();
+        machine.input(input);
+        let mut configurations = vec![(machine.current_state.clone(), machine.tape.clone())];
+        for _ in 0..max_steps {
+            if !matches!(machine.run_once(), Ok(false)) {
+                break;
+            }
+            configurations.push((machine.current_state.clone(), machine.tape.clone()));
+        }
+        // the cells filled or under the head in any frame, for each tape
+        let empty = self.pattern_config.empty;
+        let ranges = (0..machine.tape.len())
+            .map(|i| {
+                let frozen = configurations.iter().map(|(_, tape)| tape[i].freeze(empty));
+                let start = frozen.clone().map(|f| f.range.start).min().unwrap_or(0);
+                let end = frozen.map(|f| f.range.end).max().unwrap_or(1);
+                start..end
+            })
+            .collect::<Vec<_>>();
+        let width = ranges.iter().map(|r| r.end - r.start).max().unwrap_or(1) * CELL + 10;
+        let height = ranges.len() as isize * (CELL + 10) + 40;
+        configurations
+            .iter()
+            .map(|(state, tape)| {
+                let mut svg = format!(
+                    "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\">\n"
+                );
+                writeln!(svg, "  <text x=\"5\" y=\"20\">State: {}</text>", escape(state)).unwrap();
+                for (i, (tape, range)) in tape.iter().zip(&ranges).enumerate() {
+                    svg.push_str(&Self::svg_tape(tape, range.clone(), i, empty));
+                }
+                svg.push_str("</svg>\n");
+                svg
+            })
+            .collect()
+    }
+
+    /// the cells of the tape in the range as rects with their symbols, in the row of the tape
+    fn svg_tape(tape: &Tape, range: std::ops::Range<isize>, row: usize, empty: char) -> String {
+        let frozen = tape.freeze_range(empty, range.clone());
+        let y = 30 + row as isize * (CELL + 10);
+        let mut svg = String::new();
+        for (index, c) in range.zip(frozen.tape.chars()) {
+            let x = 5 + (index - frozen.range.start) * CELL;
+            let fill = if index == frozen.head {
+                "yellow"
+            } else {
+                "white"
+            };
+            writeln!(
+                svg,
+                "  <rect x=\"{x}\" y=\"{y}\" width=\"{CELL}\" height=\"{CELL}\" \
+                 fill=\"{fill}\" stroke=\"black\"/>"
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+                x + CELL / 2,
+                y + CELL * 2 / 3,
+                escape(&c.to_string())
+            )
+            .unwrap();
+        }
+        svg
+    }
+}
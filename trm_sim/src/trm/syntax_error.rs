@@ -1,5 +1,6 @@
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
 
 /// error type for syntax errors
 #[derive(Debug, Clone)]
@@ -8,14 +9,52 @@ pub enum SyntaxErrorType {
     TransitionConsumeProduceNotMatch,
     /// the transition direction is not found
     TransitionDirectionNotFound,
+    /// the transition weight is negative or not finite
+    TransitionWeightNotValid,
+    /// the transition produces a wildcard not kept from the same wildcard consumed
+    TransitionProduceNotValid,
+    /// the transition ends with a lone escape char
+    TransitionEscapeNotValid,
     /// the transition next state is not found
     TransitionNextStateNotFound,
+    /// the transition consumes a different number of tapes than the machine has
+    TransitionTapeNumberNotMatch,
+    /// the transition writes a tape that is read only
+    TransitionWritesReadonlyTape,
+    /// the transition refers to a symbol group not in the config, or uses it wrongly
+    TransitionGroupNotValid,
+    /// the regex of the transition `cons` does not compile
+    TransitionRegexNotValid,
+    /// the transition pushes or pops without a valid stack tape, or pushes a meta char
+    TransitionStackEffectNotValid,
     /// the syntax is not valid
     SyntaxNotValid(String),
     /// the format is not provided
     FormatNotProvided,
+    /// the model has fields not known in strict mode
+    UnknownField(String),
+    /// the accept condition of a state is not known
+    StateAcceptConditionNotValid,
+    /// two states have the same name, given or by position
+    StateNameDuplicated,
+    /// the blank of a state is a meta char of the config
+    StateBlankNotValid,
     /// start state is not found or more than one
     StartStateError,
+    /// the model declares a format version newer than supported
+    VersionNotSupported(u32),
+    /// the model is larger than the limits allow
+    ModelLimitExceeded,
+    /// the snapshot does not fit the machine of the model
+    SnapshotNotMatch,
+    /// a model file could not be read
+    FileNotRead,
+    /// the includes of a model form a cycle, or define a state twice
+    IncludeNotValid,
+    /// a state template has an empty parameter, or defines a state already defined
+    TemplateNotValid,
+    /// the escape char of the config is also the blank or a wildcard
+    ConfigEscapeNotValid,
 }
 
 /// error struct for syntax errors
@@ -25,6 +64,25 @@ pub struct SyntaxError {
     pub error_type: SyntaxErrorType,
     /// the error message
     pub message: String,
+    /// the underlying error, like the deserializer error
+    source: Option<Arc<dyn Error + Send + Sync>>,
+}
+
+impl SyntaxError {
+    /// create a new syntax error without source
+    pub fn new(error_type: SyntaxErrorType, message: String) -> Self {
+        Self {
+            error_type,
+            message,
+            source: None,
+        }
+    }
+
+    /// set the underlying error of this error
+    pub fn with_source(mut self, source: impl Error + Send + Sync + 'static) -> Self {
+        self.source = Some(Arc::new(source));
+        self
+    }
 }
 
 impl Display for SyntaxError {
@@ -33,4 +91,8 @@ impl Display for SyntaxError {
     }
 }
 
-impl Error for SyntaxError {}
+impl Error for SyntaxError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_deref().map(|e| e as &(dyn Error + 'static))
+    }
+}
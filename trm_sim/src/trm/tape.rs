@@ -1,19 +1,25 @@
 //! this module contains the tape struct and its methods
 
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
 use std::ops::Range;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use super::Direction;
 
 /// a tape is a vector of symbols with a head
 /// that can move left and right,
-/// the tape is infinite in both directions
+/// the tape is infinite in both directions.
+/// The symbols are shared between clones until one of them is written,
+/// so branches of a nondeterministic run only copy the tapes they change
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tape {
-    /// the symbols on the tape
-    tape: VecDeque<Option<char>>,
+    /// the symbols on the tape, copied on write when shared
+    tape: Rc<VecDeque<Option<char>>>,
     /// current position of the head.
     /// this index is for inside, which means the index of the vector.
     /// the outside index usually has special meaning, so it can be negative.
@@ -21,6 +27,16 @@ pub struct Tape {
     /// the index of the first symbol on the tape
     /// head + offset = tape index from outside
     offset: isize,
+    /// changed to a number never used before on every change of the tape,
+    /// so tapes of the same generation are the same
+    #[serde(skip, default = "next_generation")]
+    generation: u64,
+}
+
+/// a generation for a tape not used by any other tape
+fn next_generation() -> u64 {
+    static GENERATION: AtomicU64 = AtomicU64::new(0);
+    GENERATION.fetch_add(1, Ordering::Relaxed)
 }
 
 /// frozen tape is a tape that can't be modified,
@@ -28,7 +44,7 @@ pub struct Tape {
 /// It only contain the non-empty and head range of the tape.
 /// It is the mainly way to get a `Tape`'s inner data.
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FrozenTape {
     /// the tape's non-empty symbols
     pub tape: String,
@@ -54,12 +70,60 @@ impl Tape {
         }
 
         Self {
-            tape: data,
+            tape: Rc::new(data),
             head: 0,
             offset: 0,
+            generation: next_generation(),
         }
     }
 
+    /// creates a new tape with the given string starting at the outside index `start`,
+    /// the head is at the outside index 0
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::Tape;
+    /// let tape = Tape::new_at("abc", -3);
+    /// assert_eq!(tape.get(-3), Some('a'));
+    /// assert_eq!(tape.head(), 0);
+    /// assert_eq!(tape.read(), None);
+    /// ```
+    pub fn new_at(s: &str, start: isize) -> Self {
+        let symbols = s.chars().collect::<Vec<_>>();
+        if symbols.is_empty() {
+            return Self::new("");
+        }
+        let first = start.min(0);
+        let last = (start + symbols.len() as isize).max(1);
+        let tape = (first..last)
+            .map(|i| {
+                usize::try_from(i - start)
+                    .ok()
+                    .and_then(|i| symbols.get(i).copied())
+            })
+            .collect();
+        Self {
+            tape: Rc::new(tape),
+            head: (-first) as usize,
+            offset: first,
+            generation: next_generation(),
+        }
+    }
+
+    /// returns the symbol at the outside index,
+    /// `None` for cells never written
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::Tape;
+    /// let tape = Tape::new("01");
+    /// assert_eq!(tape.get(1), Some('1'));
+    /// assert_eq!(tape.get(-1), None);
+    /// ```
+    pub fn get(&self, index: isize) -> Option<char> {
+        usize::try_from(index - self.offset)
+            .ok()
+            .and_then(|i| self.tape.get(i).copied().flatten())
+    }
+
     /// returns the symbol under the head
     /// # Example
     /// ```
@@ -93,9 +157,13 @@ impl Tape {
     /// assert_eq!(tape.read(), Some('1'));
     /// ```
     pub fn write(&mut self, c: char) {
-        if let Some(s) = self.tape.get_mut(self.head) {
-            *s = Some(c);
+        // writing the symbol already there keeps the symbols shared
+        if self.read() == Some(c) {
+            return;
         }
+        self.fill_to_head();
+        let head = self.head;
+        self.cells()[head] = Some(c);
     }
 
     /// write a blank symbol under the head
@@ -108,9 +176,12 @@ impl Tape {
     /// ```
     /// if the head is out of bounds, adds a new symbol
     pub fn write_blank(&mut self) {
-        if let Some(s) = self.tape.get_mut(self.head) {
-            *s = None;
+        if self.tape.get(self.head) == Some(&None) {
+            return;
         }
+        self.fill_to_head();
+        let head = self.head;
+        self.cells()[head] = None;
     }
 
     /// move the head left
@@ -129,10 +200,12 @@ impl Tape {
     /// assert_eq!(tape.read(), None);
     /// ```
     pub fn move_left(&mut self) {
+        self.generation = next_generation();
+        self.fill_to_head();
         // if head is at the beginning of the tape,
         // add a new symbol to the beginning
         if self.head == 0 {
-            self.tape.push_front(None);
+            self.cells().push_front(None);
             self.offset -= 1;
         } else {
             self.head -= 1;
@@ -155,13 +228,145 @@ impl Tape {
     /// assert_eq!(tape.read(), None);
     /// ```
     pub fn move_right(&mut self) {
+        self.generation = next_generation();
+        self.fill_to_head();
         // if head is at the end of the tape, add a new symbol
-        if self.head == self.tape.len() - 1 {
-            self.tape.push_back(None);
+        if self.head + 1 == self.tape.len() {
+            self.cells().push_back(None);
         }
         self.head += 1;
     }
 
+    /// make sure the cell under the head exists,
+    /// so an emptied tape behaves like a single blank cell
+    fn fill_to_head(&mut self) {
+        while self.tape.len() <= self.head {
+            self.cells().push_back(None);
+        }
+    }
+
+    /// the symbols to modify, copied first if another tape shares them
+    fn cells(&mut self) -> &mut VecDeque<Option<char>> {
+        self.generation = next_generation();
+        Rc::make_mut(&mut self.tape)
+    }
+
+    /// empties the tape, leaving it as `Tape::new("")` would,
+    /// a single blank cell under the head at the outside index 0
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::Tape;
+    /// let mut tape = Tape::new("0101");
+    /// tape.move_left();
+    /// tape.clear();
+    /// assert_eq!(tape.read(), None);
+    /// assert_eq!(tape.head(), 0);
+    /// ```
+    pub fn clear(&mut self) {
+        self.tape = Rc::new(VecDeque::from([None]));
+        self.generation = next_generation();
+        self.head = 0;
+        self.offset = 0;
+    }
+
+    /// moves the head back to the outside index 0, keeping the symbols
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::Tape;
+    /// let mut tape = Tape::new("01");
+    /// tape.move_right();
+    /// tape.reset_head();
+    /// assert_eq!(tape.read(), Some('0'));
+    /// ```
+    pub fn reset_head(&mut self) {
+        // the cells left of the tape up to index 0, if the tape starts right of it
+        for _ in 0..self.offset.max(0) {
+            self.cells().push_front(None);
+        }
+        self.offset = self.offset.min(0);
+        self.head = (-self.offset) as usize;
+        self.generation = next_generation();
+        self.fill_to_head();
+    }
+
+    /// returns the outside index of the head
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::Tape;
+    /// let mut tape = Tape::new("0101");
+    /// tape.move_left();
+    /// assert_eq!(tape.head(), -1);
+    /// ```
+    pub fn head(&self) -> isize {
+        self.head as isize + self.offset
+    }
+
+    /// returns the raw symbols of the tape,
+    /// `None` for cells never written
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::Tape;
+    /// let mut tape = Tape::new("01");
+    /// tape.move_left();
+    /// assert_eq!(tape.contents(), vec![None, Some('0'), Some('1')]);
+    /// ```
+    pub fn contents(&self) -> Vec<Option<char>> {
+        self.tape.iter().copied().collect()
+    }
+
+    /// returns the inside index of the head,
+    /// which is the index into `contents`
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::Tape;
+    /// let mut tape = Tape::new("01");
+    /// tape.move_right();
+    /// assert_eq!(tape.head_index(), 1);
+    /// ```
+    pub fn head_index(&self) -> usize {
+        self.head
+    }
+
+    /// if the head is on a cell or right after the last one, as on a tape read back without cells,
+    /// and its outside index fits. A tape read back with its head far away is not valid,
+    /// moving it would fill every cell up to the head
+    pub(crate) fn is_valid(&self) -> bool {
+        self.head <= self.tape.len()
+            && isize::try_from(self.head).is_ok_and(|head| head.checked_add(self.offset).is_some())
+    }
+
+    /// returns the cells from `radius` cells left of the head
+    /// to `radius` cells right of it, cells never written are `None`
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::Tape;
+    /// let mut tape = Tape::new("01");
+    /// assert_eq!(tape.window(1), vec![None, Some('0'), Some('1')]);
+    /// ```
+    pub fn window(&self, radius: usize) -> Vec<Option<char>> {
+        let radius = radius as isize;
+        (-radius..=radius)
+            .map(|i| {
+                let index = self.head as isize + i;
+                usize::try_from(index)
+                    .ok()
+                    .and_then(|i| self.tape.get(i).copied().flatten())
+            })
+            .collect()
+    }
+
+    /// returns the distance from the head to the farthest written cell,
+    /// so `window(reach())` covers every written cell
+    pub(crate) fn reach(&self) -> usize {
+        self.tape
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_some())
+            .map(|(i, _)| i.abs_diff(self.head))
+            .max()
+            .unwrap_or(0)
+    }
+
     /// move the head with given direction,
     /// stays if the direction is `Stay`
     /// # Example
@@ -180,7 +385,7 @@ impl Tape {
     }
 
     /// returns the tape's frozen version,
-    /// removing None on the tape.
+    /// removing None and empty symbols on both ends of the tape.
     /// But replacing them with the given empty symbol if needed.
     /// # Example
     /// ```
@@ -192,34 +397,244 @@ impl Tape {
     /// assert_eq!(frozen.head, -1);
     /// ```
     pub fn freeze(&self, empty: char) -> FrozenTape {
+        let (start, end) = self.filled_bounds(Some(empty));
+        // get the non-empty symbols
+        let tape: String = (start..=end)
+            .map(|i| self.tape.get(i).copied().flatten().unwrap_or(empty))
+            .collect();
+        // get the outside index of head
+        let head = self.head as isize + self.offset;
+        // get the range of the tape
+        let range = start as isize + self.offset..end as isize + self.offset + 1;
+
+        FrozenTape { tape, head, range }
+    }
+
+    /// returns the frozen view of only the outside indexes in `range`,
+    /// cells never written are the given empty symbol.
+    /// The head is reported even if it is outside the range
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::Tape;
+    /// let tape = Tape::new("0101");
+    /// let frozen = tape.freeze_range('_', -1..2);
+    /// assert_eq!(frozen.tape, "_01");
+    /// assert_eq!(frozen.range, -1..2);
+    /// ```
+    pub fn freeze_range(&self, empty: char, range: Range<isize>) -> FrozenTape {
+        let tape = range
+            .clone()
+            .map(|i| self.get(i).unwrap_or(empty))
+            .collect();
+        FrozenTape {
+            tape,
+            head: self.head(),
+            range,
+        }
+    }
+
+    /// removes the cells never written or written blank on both ends of the tape,
+    /// keeping the cell under the head.
+    /// A written empty symbol is a literal, so it is kept.
+    /// The outside index of the head and the frozen tape do not change.
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::Tape;
+    /// let mut tape = Tape::new_at("01", 2);
+    /// tape.move_right();
+    /// tape.move_right();
+    /// tape.trim();
+    /// assert_eq!(tape.contents(), vec![Some('0'), Some('1')]);
+    /// assert_eq!(tape.head(), 2);
+    /// ```
+    pub fn trim(&mut self) {
+        let (start, end) = self.filled_bounds(None);
+        self.fill_to_head();
+        if start == 0 && end + 1 == self.tape.len() {
+            return;
+        }
+        let cells = self.cells();
+        cells.truncate(end + 1);
+        cells.drain(..start);
+        self.head -= start;
+        self.offset += start as isize;
+    }
+
+    /// returns the inside indexes of the first and the last non-empty symbols,
+    /// widened to include the head. The `empty` symbol, if any, counts as empty too
+    fn filled_bounds(&self, empty: Option<char>) -> (usize, usize) {
         // get the first non-empty symbol before head
+        let is_filled = |o: &Option<char>| o.is_some_and(|c| Some(c) != empty);
         let start = self
             .tape
             .iter()
             .take(self.head)
-            .position(Option::is_some)
+            .position(is_filled)
             .unwrap_or(self.head);
         // get the last non-empty symbol after head
         let end = self
             .tape
             .iter()
             .skip(self.head + 1)
-            .rposition(Option::is_some)
+            .rposition(is_filled)
             .map_or(self.head, |i| i + self.head + 1);
-        // get the non-empty symbols
-        let tape: String = self
-            .tape
+        (start, end)
+    }
+}
+
+/// the last frozen view of every tape of a machine with the generation of the tape it shows,
+/// a view is followed along a step while it is up to date, and frozen again otherwise
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FrozenCache {
+    views: RefCell<Vec<Option<(u64, FrozenView)>>>,
+}
+
+/// the symbols of a frozen tape kept in a deque, so a step only edits its ends,
+/// the `FrozenTape` is only built from it when asked for
+#[derive(Debug, Clone)]
+struct FrozenView {
+    /// the symbols from the outside index `start`, blanks as the empty symbol
+    cells: VecDeque<char>,
+    /// the outside index of head
+    head: isize,
+    /// the outside index of the first symbol
+    start: isize,
+}
+
+impl FrozenCache {
+    /// returns the frozen tapes, freezing only the tapes without an up to date view
+    pub(crate) fn get(&self, tapes: &[Tape], empty: char) -> Vec<FrozenTape> {
+        let mut views = self.views.borrow_mut();
+        views.resize(tapes.len(), None);
+        views
+            .iter_mut()
+            .zip(tapes)
+            .map(|(view, tape)| match view {
+                Some((generation, v)) if *generation == tape.generation => v.render(),
+                _ => {
+                    let f = tape.freeze(empty);
+                    *view = Some((tape.generation, FrozenView::new(&f)));
+                    f
+                }
+            })
+            .collect()
+    }
+
+    /// returns which views are up to date, to follow them after a step
+    pub(crate) fn fresh(&self, tapes: &[Tape]) -> Vec<bool> {
+        self.views
+            .borrow()
             .iter()
-            .skip(start)
-            .take(end - start + 1)
-            .map(|o| o.unwrap_or(empty))
-            .collect();
-        // get the outside index of head
-        let head = self.head as isize + self.offset;
-        // get the range of the tape
-        let range = start as isize + self.offset..end as isize + self.offset + 1;
+            .zip(tapes)
+            .map(|(view, tape)| view.as_ref().is_some_and(|(g, _)| *g == tape.generation))
+            .collect()
+    }
 
-        FrozenTape { tape, head, range }
+    /// follows the views that were up to date before a step, and drops the others
+    pub(crate) fn follow(&self, fresh: &[bool], tapes: &[Tape], empty: char) {
+        for ((view, tape), fresh) in self.views.borrow_mut().iter_mut().zip(tapes).zip(fresh) {
+            match view {
+                Some((generation, v)) if *fresh => {
+                    v.follow(tape, empty);
+                    *generation = tape.generation;
+                }
+                _ => *view = None,
+            }
+        }
+    }
+}
+
+impl FrozenView {
+    /// keeps the symbols of a frozen tape
+    fn new(frozen: &FrozenTape) -> Self {
+        Self {
+            cells: frozen.tape.chars().collect(),
+            head: frozen.head,
+            start: frozen.range.start,
+        }
+    }
+
+    /// builds the frozen tape of the view
+    fn render(&self) -> FrozenTape {
+        FrozenTape {
+            tape: self.cells.iter().collect(),
+            head: self.head,
+            range: self.start..self.start + self.cells.len() as isize,
+        }
+    }
+
+    /// updates the view after a step of the tape it was frozen from,
+    /// which writes at most the cell under the old head and moves the head by at most one.
+    /// It is the same as freezing the tape again, editing only the ends of the view
+    fn follow(&mut self, tape: &Tape, empty: char) {
+        let written = tape.get(self.head).unwrap_or(empty);
+        self.cells[(self.head - self.start) as usize] = written;
+        let head = tape.head();
+        if head < self.start {
+            self.cells.push_front(empty);
+            self.start = head;
+        } else if head >= self.start + self.cells.len() as isize {
+            self.cells.push_back(empty);
+        }
+        self.head = head;
+        // blanks at the ends are only kept under the head
+        while self.start < head && self.cells.front() == Some(&empty) {
+            self.cells.pop_front();
+            self.start += 1;
+        }
+        while self.start + self.cells.len() as isize - 1 > head && self.cells.back() == Some(&empty)
+        {
+            self.cells.pop_back();
+        }
+    }
+}
+
+impl FrozenTape {
+    /// parses a tape shown with the head cell in brackets, like `11[0]1`,
+    /// the reverse of its `Display` for a tape starting at the outside index 0.
+    /// The first cell is at the outside index 0, the head at the bracketed cell
+    /// # Example
+    /// ```
+    /// use trm_sim::trm::{FrozenTape, Tape};
+    /// let mut tape = Tape::new("1101");
+    /// tape.move_right();
+    /// tape.move_right();
+    /// assert_eq!(tape.freeze('_'), FrozenTape::from_display("11[0]1"));
+    /// ```
+    /// # Panics
+    /// if no cell is in brackets
+    pub fn from_display(s: &str) -> Self {
+        let chars = s.chars().collect::<Vec<_>>();
+        let head = (0..chars.len())
+            .find(|&i| chars[i] == '[' && chars.get(i + 2) == Some(&']'))
+            .unwrap_or_else(|| panic!("no head cell in brackets in `{s}`"));
+        let tape = chars
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != head && i != head + 2)
+            .map(|(_, c)| c)
+            .collect::<String>();
+        let len = tape.chars().count() as isize;
+        Self {
+            tape,
+            head: head as isize,
+            range: 0..len,
+        }
+    }
+}
+
+/// shows the tape content with the head cell in brackets, like `01[0]1`
+impl Display for FrozenTape {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let head = self.head - self.range.start;
+        for (i, c) in self.tape.chars().enumerate() {
+            if i as isize == head {
+                write!(f, "[{c}]")?;
+            } else {
+                write!(f, "{c}")?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -246,7 +661,7 @@ mod tests {
 
     #[test]
     fn test_vec_range() {
-        let vec1 = vec![1, 2, 3, 4, 5];
+        let vec1 = [1, 2, 3, 4, 5];
         let len = vec1.len();
         for i in 0..len {
             assert!(vec1.get(i).is_some());
@@ -257,7 +672,7 @@ mod tests {
         }
     }
 
-    use super::Tape;
+    use super::{FrozenTape, Tape};
 
     #[test]
     fn test_tape_usage() {
@@ -306,6 +721,175 @@ mod tests {
         assert_eq!(null_tape3.read(), Some('1'));
     }
 
+    #[test]
+    fn test_tape_contents() {
+        let mut tape = Tape::new("0");
+        tape.write('_');
+        tape.move_right();
+        assert_eq!(tape.contents(), vec![Some('_'), None]);
+        assert_eq!(tape.head_index(), 1);
+        tape.move_left();
+        tape.move_left();
+        assert_eq!(tape.contents(), vec![None, Some('_'), None]);
+        assert_eq!(tape.head_index(), 0);
+    }
+
+    #[test]
+    fn test_tape_emptied() {
+        // a tape without cells, as it may be read back
+        let empty = || serde_json::from_str::<Tape>(r#"{"tape": [], "head": 0, "offset": 0}"#);
+        let mut tape = empty().unwrap();
+        assert_eq!(tape.read(), None);
+        assert_eq!(tape.freeze('_').tape, "_");
+        tape.move_right();
+        assert_eq!(tape.read(), None);
+        assert_eq!(tape.contents(), vec![None, None]);
+
+        let mut tape = empty().unwrap();
+        tape.write('1');
+        assert_eq!(tape.read(), Some('1'));
+
+        let mut tape = empty().unwrap();
+        tape.move_left();
+        assert_eq!(tape.contents(), vec![None, None]);
+        assert_eq!(tape.head(), -1);
+    }
+
+    #[test]
+    fn test_tape_new_at() {
+        let tape = Tape::new_at("ab", 2);
+        assert_eq!(tape.contents(), vec![None, None, Some('a'), Some('b')]);
+        assert_eq!(tape.head(), 0);
+        assert_eq!(tape.freeze('_').tape, "__ab");
+
+        let tape = Tape::new_at("ab", -1);
+        assert_eq!(tape.contents(), vec![Some('a'), Some('b')]);
+        assert_eq!(tape.read(), Some('b'));
+
+        let tape = Tape::new_at("", -2);
+        assert_eq!(tape.contents(), vec![None]);
+        assert_eq!(tape.head(), 0);
+    }
+
+    #[test]
+    fn test_frozen_tape_from_display() {
+        for s in ["11[0]1", "[_]", "[[]]", "0[1]"] {
+            assert_eq!(FrozenTape::from_display(s).to_string(), s);
+        }
+        let mut tape = Tape::new("01");
+        tape.move_right();
+        tape.move_right();
+        let frozen = tape.freeze('_');
+        assert_eq!(FrozenTape::from_display(&frozen.to_string()), frozen);
+        assert_eq!(frozen, FrozenTape::from_display("01[_]"));
+    }
+
+    #[test]
+    fn test_tape_trim() {
+        let mut tape = Tape::new("_0_");
+        for _ in 0..3 {
+            tape.move_left();
+        }
+        tape.trim();
+        assert_eq!(
+            tape.contents(),
+            vec![None, None, None, Some('_'), Some('0'), Some('_')]
+        );
+        assert_eq!(tape.head(), -3);
+
+        let mut tape = Tape::new("0");
+        tape.move_right();
+        tape.move_right();
+        tape.move_left();
+        tape.trim();
+        assert_eq!(tape.contents(), vec![Some('0'), None]);
+
+        let mut tape = Tape::new("01");
+        tape.cells().clear();
+        tape.trim();
+        assert_eq!(tape.contents(), vec![None]);
+        assert_eq!(tape.freeze('_').tape, "_");
+    }
+
+    #[test]
+    fn test_tape_shared_until_written() {
+        let mut tape = Tape::new("01");
+        let mut branch = tape.clone();
+        branch.move_right();
+        branch.write('1');
+        assert!(std::rc::Rc::ptr_eq(&tape.tape, &branch.tape));
+        branch.write('0');
+        assert!(!std::rc::Rc::ptr_eq(&tape.tape, &branch.tape));
+        assert_eq!(tape.freeze('_'), Tape::new("01").freeze('_'));
+        assert_eq!(branch.contents(), vec![Some('0'), Some('0')]);
+        tape.write_blank();
+        assert_eq!(tape.contents(), vec![None, Some('1')]);
+        assert_eq!(branch.contents(), vec![Some('0'), Some('0')]);
+    }
+
+    #[test]
+    fn test_tape_clear() {
+        let mut tape = Tape::new("01");
+        tape.move_left();
+        tape.write('1');
+        tape.clear();
+        assert_eq!(tape.read(), None);
+        assert_eq!(tape.contents(), Tape::new("").contents());
+        assert_eq!(tape.head(), 0);
+        assert_eq!(tape.offset, 0);
+    }
+
+    #[test]
+    fn test_tape_reset_head() {
+        let mut tape = Tape::new("01");
+        tape.move_left();
+        tape.move_left();
+        tape.reset_head();
+        assert_eq!(tape.head(), 0);
+        assert_eq!(tape.read(), Some('0'));
+        assert_eq!(tape.contents(), vec![None, None, Some('0'), Some('1')]);
+
+        // the tape starts right of index 0 after trimming
+        let mut tape = Tape::new_at("01", 1);
+        tape.move_right();
+        tape.move_right();
+        tape.trim();
+        tape.reset_head();
+        assert_eq!(tape.head(), 0);
+        assert_eq!(tape.read(), None);
+        assert_eq!(tape.get(1), Some('0'));
+        assert_eq!(tape.freeze('_').tape, "_01");
+    }
+
+    #[test]
+    fn test_tape_freeze_range() {
+        let mut tape = Tape::new("abcdefgh");
+        for _ in 0..6 {
+            tape.move_right();
+        }
+        let frozen = tape.freeze_range('_', 2..5);
+        assert_eq!(frozen.tape, "cde");
+        assert_eq!(frozen.head, 6);
+        assert_eq!(frozen.range, 2..5);
+        assert_eq!(frozen.to_string(), "cde");
+
+        let frozen = tape.freeze_range('_', 6..10);
+        assert_eq!(frozen.tape, "gh__");
+        assert_eq!(frozen.to_string(), "[g]h__");
+        assert_eq!(tape.freeze_range('_', -2..1).tape, "__a");
+        assert_eq!(tape.freeze_range('_', 3..3).tape, "");
+    }
+
+    #[test]
+    fn test_frozen_tape_display() {
+        let mut tape = Tape::new("0101");
+        tape.move_right();
+        assert_eq!(tape.freeze('_').to_string(), "0[1]01");
+        tape.move_left();
+        tape.move_left();
+        assert_eq!(tape.freeze('_').to_string(), "[_]0101");
+    }
+
     #[test]
     fn test_tape_freeze() {
         let tape = Tape::new(" 0101 ");
@@ -0,0 +1,122 @@
+//! This module contains state templates,
+//! states instantiated once per value of a parameter before the machine is built.
+
+use crate::trm::{MachineModel, ModelLimits, StateSerde, SyntaxError, SyntaxErrorType};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// states written once for a family of states, like `cmp{i}` for `i` in `0`, `1`.
+/// Every `{param}` in the names, symbols and other strings of the states
+/// is replaced by each value in turn, so the parameter may not be the name of a symbol group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateTemplate {
+    /// the name of the parameter, written as `{param}` in the states
+    pub param: String,
+    /// the values of the parameter, one instance of the states for each in order
+    pub values: Vec<String>,
+    /// the states to instantiate
+    #[serde(default, alias = "states")]
+    pub state: Vec<StateSerde>,
+}
+
+impl MachineModel {
+    /// appends the instances of the templates to the states, in the order of the templates
+    /// and their values, and removes the templates
+    /// # Errors
+    /// * `TemplateNotValid` - if a parameter is empty or the name of a symbol group,
+    ///   or an instance names a state already defined
+    /// * `ModelLimitExceeded` - if the instances would make the model larger than the limits,
+    ///   checked before any of them is made
+    pub(crate) fn expand_templates(&mut self, limits: &ModelLimits) -> Result<(), SyntaxError> {
+        self.check_template_limits(limits)?;
+        for template in std::mem::take(&mut self.template) {
+            let error =
+                |message: String| SyntaxError::new(SyntaxErrorType::TemplateNotValid, message);
+            if template.param.is_empty() {
+                return Err(error("a template has an empty parameter".to_string()));
+            }
+            if self.pattern_config.groups.contains_key(&template.param) {
+                return Err(error(format!(
+                    "template parameter `{{{}}}` is also a symbol group",
+                    template.param
+                )));
+            }
+            let placeholder = format!("{{{}}}", template.param);
+            for value in &template.values {
+                for state in &template.state {
+                    let state = instantiate(state, &placeholder, value).map_err(|e| {
+                        error(format!(
+                            "template `{placeholder}` = `{value}` is not a state"
+                        ))
+                        .with_source(e)
+                    })?;
+                    if !state.name().is_empty()
+                        && self.state.iter().any(|s| s.name() == state.name())
+                    {
+                        return Err(error(format!(
+                            "template `{placeholder}` = `{value}` defines state `{}` again",
+                            state.name()
+                        )));
+                    }
+                    self.state.push(state);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// check the states and transitions with the instances of the templates
+    /// are not more than the limits
+    fn check_template_limits(&self, limits: &ModelLimits) -> Result<(), SyntaxError> {
+        if self.template.is_empty() {
+            return Ok(());
+        }
+        let count = |states: &[StateSerde]| {
+            let transitions = states.iter().map(|s| s.transitions().len()).sum::<usize>();
+            (states.len(), transitions)
+        };
+        let (mut states, mut transitions) = count(&self.state);
+        for template in &self.template {
+            let (s, t) = count(&template.state);
+            let n = template.values.len();
+            states = states.saturating_add(n.saturating_mul(s));
+            transitions = transitions.saturating_add(n.saturating_mul(t));
+        }
+        let sizes = [
+            ("states", states, limits.max_states),
+            ("transitions", transitions, limits.max_transitions),
+        ];
+        match sizes.into_iter().find(|(_, size, max)| size > max) {
+            Some((what, size, max)) => Err(SyntaxError::new(
+                SyntaxErrorType::ModelLimitExceeded,
+                format!("templates make {size} {what}, but at most {max} are allowed"),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// the state with every `placeholder` in its strings replaced by `value`
+fn instantiate(
+    state: &StateSerde,
+    placeholder: &str,
+    value: &str,
+) -> Result<StateSerde, serde_json::Error> {
+    let mut state = serde_json::to_value(state)?;
+    substitute(&mut state, placeholder, value);
+    serde_json::from_value(state)
+}
+
+/// replaces `placeholder` by `value` in every string of the json value
+fn substitute(json: &mut Value, placeholder: &str, value: &str) {
+    match json {
+        Value::String(s) => *s = s.replace(placeholder, value),
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| substitute(item, placeholder, value)),
+        Value::Object(fields) => fields
+            .values_mut()
+            .for_each(|field| substitute(field, placeholder, value)),
+        _ => {}
+    }
+}
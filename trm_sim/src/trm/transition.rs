@@ -5,17 +5,37 @@ use crate::trm::{Pattern, PatternConfig};
 use crate::trm::syntax_error::{SyntaxError, SyntaxErrorType};
 
 /// a turing machine transition
+#[derive(Clone)]
 pub struct Transition {
     /// the symbols to consume
     pub consume: Vec<char>,
+    /// which consumed symbols are escaped, so they are literals
+    pub consume_escaped: Vec<bool>,
     /// the pattern to consume
     pub consume_pattern: Vec<Box<dyn Pattern>>,
+    /// the regex consumed, without the slashes, if `cons` is a regex.
+    /// It consumes a wildcard then, so only the pattern tells what it matches
+    pub consume_regex: Option<String>,
     /// the symbols to produce
     pub produce: Vec<char>,
+    /// which produced symbols are escaped, so they are always written as is
+    pub produce_escaped: Vec<bool>,
     /// the direction to move
     pub direction: Vec<Direction>,
     /// the next state
     pub next_state_name: String,
+    /// the id of the next state in its machine, given when the machine is built,
+    /// `None` if the next state does not exist
+    pub(crate) next_id: Option<usize>,
+    /// the weight for probabilistic runs, `None` means 1
+    pub weight: Option<f64>,
+    /// an optional name to find the transition in diagnostics
+    pub label: Option<String>,
+    /// an optional comment printed in traces when the transition is taken
+    pub note: Option<String>,
+    /// the literal transitions folded into this one by `Machine::fold_transitions`,
+    /// empty if it is not folded
+    pub folded: Vec<Transition>,
 }
 
 /// a helper struct for serde transition
@@ -24,120 +44,770 @@ pub struct TransitionSerde {
     /// the symbols to consume
     #[serde(alias = "consume")]
     cons: String,
-    /// the symbols to produce
-    #[serde(alias = "produce")]
-    prod: String,
-    /// the direction to move
-    #[serde(rename = "move")]
-    next_direction: String,
-    /// the next state
-    #[serde(rename = "next")]
-    next_state_name: String,
+    /// the symbols to produce, the consumed symbols are kept if omitted
+    #[serde(default, alias = "produce", skip_serializing_if = "Option::is_none")]
+    prod: Option<String>,
+    /// the direction to move, an array of single direction tokens is joined by `,`.
+    /// The `default_move` of the config is used if omitted
+    #[serde(
+        default,
+        rename = "move",
+        deserialize_with = "moves::deserialize",
+        skip_serializing_if = "Option::is_none"
+    )]
+    next_direction: Option<String>,
+    /// the next state, by name or by its position in the model,
+    /// the state of the transition itself if omitted or empty
+    #[serde(default, rename = "next")]
+    next_state_name: StateRef,
+    /// the weight for probabilistic runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weight: Option<f64>,
+    /// an optional name to find the transition in diagnostics
+    #[serde(default, alias = "id", skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    /// an optional comment printed in traces when the transition is taken
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+    /// the symbol to push on the stack tape of the config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    push: Option<char>,
+    /// pop the top of the stack tape of the config, before pushing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pop: bool,
 }
 
 impl Transition {
+    /// rewrite the blank `from` of the symbols as the blank `to`,
+    /// a symbol `to` that is not escaped becomes an escaped literal
+    pub(crate) fn rebase_blank(&mut self, from: char, to: char) {
+        if from == to {
+            return;
+        }
+        let symbols = [
+            (&mut self.consume, &mut self.consume_escaped),
+            (&mut self.produce, &mut self.produce_escaped),
+        ];
+        for (symbols, escaped) in symbols {
+            for (c, escaped) in symbols.iter_mut().zip(escaped.iter_mut()) {
+                if *escaped {
+                    continue;
+                }
+                if *c == from {
+                    *c = to;
+                } else if *c == to {
+                    *escaped = true;
+                }
+            }
+        }
+    }
+
     /// create new transition from serde transition
     pub fn try_from_serde(
         trans: TransitionSerde,
-        config: PatternConfig,
+        config: &PatternConfig,
     ) -> Result<Self, SyntaxError> {
         trans.into_transition(config)
     }
 
-    /// get serde transition
-    pub fn to_serde(&self) -> TransitionSerde {
-        TransitionSerde::from_transition(self)
+    /// get serde transition, escaping symbols with the config escape char
+    pub fn to_serde(&self, config: &PatternConfig) -> TransitionSerde {
+        TransitionSerde::from_transition(self, config)
+    }
+
+    /// describe the transition for messages, like ``Transition `a` -> `b` `label` ``,
+    /// a folded transition consumes a class like `[01]`
+    pub fn describe(&self) -> String {
+        let cons = if let Some(regex) = &self.consume_regex {
+            format!("/{regex}/")
+        } else if self.folded.is_empty() {
+            self.consume.iter().collect::<String>()
+        } else {
+            format!(
+                "[{}]",
+                self.folded
+                    .iter()
+                    .flat_map(|t| &t.consume)
+                    .collect::<String>()
+            )
+        };
+        describe(
+            &cons,
+            &self.produce.iter().collect::<String>(),
+            self.label.as_deref(),
+        )
+    }
+
+    /// the transitions this one stands for, itself if it is not folded
+    pub(crate) fn sources(&self) -> &[Transition] {
+        if self.folded.is_empty() {
+            std::slice::from_ref(self)
+        } else {
+            &self.folded
+        }
+    }
+
+    /// the consumed symbols as written in a model, escaped with the config escape char,
+    /// a folded transition consumes a class like `[01]`
+    pub(crate) fn cons_string(&self, config: &PatternConfig) -> String {
+        if let Some(regex) = &self.consume_regex {
+            format!("/{regex}/")
+        } else if self.folded.is_empty() {
+            config.join_escapes(&self.consume, &self.consume_escaped)
+        } else {
+            let class = self
+                .folded
+                .iter()
+                .map(|t| t.cons_string(config))
+                .collect::<String>();
+            format!("[{class}]")
+        }
+    }
+}
+
+/// a state referred to by a transition, by name or by its position in the model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum StateRef {
+    Name(String),
+    Index(usize),
+}
+
+impl Default for StateRef {
+    fn default() -> Self {
+        StateRef::Name(String::new())
+    }
+}
+
+/// one symbol of `cons` or `prod` with whether it is escaped,
+/// or a reference to a group of symbols in the config like `{digit}`
+#[derive(Clone)]
+enum GroupToken {
+    Symbol(char, bool),
+    Group(String),
+}
+
+/// the regex of a `cons` without the slashes,
+/// with the wildcard consumed in its place and the pattern it compiles to
+type ConsRegex = (String, char, Box<dyn Pattern>);
+
+/// compile the regex of a `cons` into the pattern of its tape
+#[cfg(feature = "regex")]
+fn compile_regex(
+    source: &str,
+    error: impl Fn(String) -> SyntaxError,
+) -> Result<Box<dyn Pattern>, SyntaxError> {
+    match crate::trm::RegexPattern::new(source) {
+        Ok(pattern) => Ok(Box::new(pattern)),
+        Err(e) => Err(error(format!("regex `{source}` is not valid")).with_source(e)),
+    }
+}
+
+/// a regex `cons` is only read with the `regex` feature
+#[cfg(not(feature = "regex"))]
+fn compile_regex(
+    source: &str,
+    error: impl Fn(String) -> SyntaxError,
+) -> Result<Box<dyn Pattern>, SyntaxError> {
+    Err(error(format!(
+        "is the regex `/{source}/`, which needs the `regex` feature"
+    )))
+}
+
+/// the name of an unnamed state at the position in the model
+pub(crate) fn auto_name(i: usize) -> String {
+    format!("s{i}")
+}
+
+/// describe a transition for messages, with its label if any
+fn describe(cons: &str, prod: &str, label: Option<&str>) -> String {
+    match label {
+        Some(label) => format!("Transition `{cons}` -> `{prod}` `{label}`"),
+        None => format!("Transition `{cons}` -> `{prod}`"),
     }
 }
 
 impl TransitionSerde {
+    /// create a serde transition in code, without weight, label or note
+    pub fn new(cons: &str, prod: &str, next_direction: &str, next_state_name: &str) -> Self {
+        Self {
+            cons: cons.to_string(),
+            prod: Some(prod.to_string()),
+            next_direction: Some(next_direction.to_string()),
+            next_state_name: StateRef::Name(next_state_name.to_string()),
+            weight: None,
+            label: None,
+            note: None,
+            push: None,
+            pop: false,
+        }
+    }
+
+    /// compile `push` and `pop` into writing and moving on the stack tape of the config,
+    /// what the transition writes and moves there is replaced.
+    /// Pushing without popping first moves right to a state writing the symbol,
+    /// returned with its only transition to be added to the model
+    pub(crate) fn expand_stack(
+        &mut self,
+        config: &PatternConfig,
+    ) -> Result<Option<(String, TransitionSerde)>, SyntaxError> {
+        if self.push.is_none() && !self.pop {
+            return Ok(None);
+        }
+        let describe = self.describe();
+        let error = |message: String| {
+            SyntaxError::new(
+                SyntaxErrorType::TransitionStackEffectNotValid,
+                format!("{describe} {message}"),
+            )
+        };
+        // one token per tape, groups are expanded later
+        let consume = self.tapes_tokens(&self.cons, config)?;
+        let stack = config
+            .stack_tape
+            .ok_or_else(|| error("pushes or pops, but no `stack_tape` is set".to_string()))?;
+        if stack >= consume.len() {
+            return Err(error(format!(
+                "has {} tapes, there is no stack tape {stack}",
+                consume.len()
+            )));
+        }
+        if let Some(c) = self.push.filter(|c| config.is_meta(*c)) {
+            return Err(error(format!("pushes the meta symbol `{c}`")));
+        }
+        let mut produce = match &self.prod {
+            Some(prod) => self.tapes_tokens(prod, config)?,
+            None => consume.clone(),
+        };
+        let moves = self.moves(consume.len(), config)?;
+        let mut direction = self.get_direction(&moves, config)?;
+        if direction.len() != consume.len() {
+            return Err(error(format!(
+                "consume do not match move direction `{moves}`"
+            )));
+        }
+        // `push` of a pop writes over the old top
+        let (write, dir) = match (self.pop, self.push) {
+            (true, Some(c)) => (GroupToken::Symbol(c, false), Direction::Stay),
+            (true, None) => (GroupToken::Symbol(config.empty, false), Direction::Left),
+            (false, _) => (consume[stack].clone(), Direction::Right),
+        };
+        produce[stack] = write;
+        direction[stack] = dir;
+        self.prod = Some(Self::join_tokens(&produce, config));
+        self.next_direction = Some(Self::join_directions(&direction, config));
+        let pushed = match (self.pop, self.push.take()) {
+            (false, Some(c)) => c,
+            _ => {
+                self.pop = false;
+                return Ok(None);
+            }
+        };
+        // on the blank above the old top, keeping the other tapes
+        let any = config.any.ok_or_else(|| {
+            error("pushes without popping, which needs `any` enabled".to_string())
+        })?;
+        let StateRef::Name(next) = &self.next_state_name else {
+            unreachable!("next states are resolved to names before the stack is expanded")
+        };
+        let mut cons = vec![any; consume.len()];
+        cons[stack] = config.empty;
+        let mut prod = cons.clone();
+        prod[stack] = pushed;
+        let push = TransitionSerde::new(
+            &cons.iter().collect::<String>(),
+            &prod.iter().collect::<String>(),
+            &Self::join_directions(&vec![Direction::Stay; consume.len()], config),
+            next,
+        );
+        let name = format!("{next}~push{pushed}");
+        self.next_state_name = StateRef::Name(name.clone());
+        Ok(Some((name, push)))
+    }
+
+    /// split `cons` or `prod` into the tokens of each tape,
+    /// a group like `{digit}` is one token if the config has groups
+    fn tapes_tokens(
+        &self,
+        s: &str,
+        config: &PatternConfig,
+    ) -> Result<Vec<GroupToken>, SyntaxError> {
+        if !config.groups.is_empty() {
+            return self.group_tokens(s, config);
+        }
+        let (symbols, escaped) = self.get_symbols(s, config)?;
+        Ok(symbols
+            .into_iter()
+            .zip(escaped)
+            .map(|(c, escaped)| GroupToken::Symbol(c, escaped))
+            .collect())
+    }
+
+    /// write tokens back as `cons` or `prod`
+    fn join_tokens(tokens: &[GroupToken], config: &PatternConfig) -> String {
+        tokens
+            .iter()
+            .map(|token| match token {
+                GroupToken::Symbol(c, escaped) => config.join_escapes(&[*c], &[*escaped]),
+                GroupToken::Group(name) => format!("{{{name}}}"),
+            })
+            .collect()
+    }
+
+    /// write directions as `move`, staying as the first symbol of the config `stay`
+    fn join_directions(direction: &[Direction], config: &PatternConfig) -> String {
+        let stay = config.stay.chars().next().unwrap_or('S');
+        direction
+            .iter()
+            .map(|d| match d {
+                Direction::Left => 'L',
+                Direction::Right => 'R',
+                Direction::Stay => stay,
+            })
+            .collect()
+    }
+
+    /// expand the groups in `cons` into one transition for each of their symbols,
+    /// taking every combination if several tapes consume groups.
+    /// The transition is kept as is if the config has no groups
+    pub(crate) fn expand_groups(self, config: &PatternConfig) -> Result<Vec<Self>, SyntaxError> {
+        if config.groups.is_empty() {
+            return Ok(vec![self]);
+        }
+        let cons = self.group_tokens(&self.cons, config)?;
+        let prod = match &self.prod {
+            Some(prod) => Some(self.group_tokens(prod, config)?),
+            None => None,
+        };
+        let mut combinations = vec![vec![]];
+        for token in &cons {
+            let options = match token {
+                GroupToken::Symbol(c, escaped) => vec![(*c, *escaped)],
+                GroupToken::Group(name) => self.group_symbols(name, config)?,
+            };
+            combinations = combinations
+                .iter()
+                .flat_map(|prefix| {
+                    options.iter().map(move |option| {
+                        let mut symbols: Vec<(char, bool)> = prefix.clone();
+                        symbols.push(*option);
+                        symbols
+                    })
+                })
+                .collect();
+        }
+        combinations
+            .into_iter()
+            .map(|symbols| {
+                let join = |symbols: Vec<(char, bool)>| {
+                    let (symbols, escaped): (Vec<_>, Vec<_>) = symbols.into_iter().unzip();
+                    config.join_escapes(&symbols, &escaped)
+                };
+                let prod = match &prod {
+                    Some(prod) => Some(join(self.group_produce(prod, &cons, &symbols)?)),
+                    None => None,
+                };
+                Ok(Self {
+                    cons: join(symbols),
+                    prod,
+                    ..self.clone()
+                })
+            })
+            .collect()
+    }
+
+    /// the number of transitions `expand_groups` makes and the symbols they consume,
+    /// without building them. A `cons` with invalid groups counts as is,
+    /// building it reports the error
+    pub(crate) fn expanded_size(&self, config: &PatternConfig) -> (usize, usize) {
+        let tokens = match config.groups.is_empty() {
+            true => None,
+            false => self.group_tokens(&self.cons, config).ok(),
+        };
+        let Some(tokens) = tokens else {
+            return (1, self.consume_len());
+        };
+        let count = tokens
+            .iter()
+            .map(|token| match token {
+                GroupToken::Symbol(..) => 1,
+                GroupToken::Group(name) => config.groups[name].chars().count(),
+            })
+            .fold(1, usize::saturating_mul);
+        (count, count.saturating_mul(tokens.len()))
+    }
+
+    /// the symbols of the group, meta chars among them are escaped.
+    /// An empty group is an error, it would drop the transition
+    fn group_symbols(
+        &self,
+        name: &str,
+        config: &PatternConfig,
+    ) -> Result<Vec<(char, bool)>, SyntaxError> {
+        if config.groups[name].is_empty() {
+            return Err(SyntaxError::new(
+                SyntaxErrorType::TransitionGroupNotValid,
+                format!(
+                    "{} group `{name}` is empty, so it would build no transition",
+                    self.describe()
+                ),
+            ));
+        }
+        config.groups[name]
+            .chars()
+            .map(|c| match (config.is_meta(c), config.escape) {
+                (true, None) => Err(SyntaxError::new(
+                    SyntaxErrorType::TransitionGroupNotValid,
+                    format!(
+                        "{} group `{name}` has the meta symbol `{c}`, which needs an escape char",
+                        self.describe()
+                    ),
+                )),
+                (escaped, _) => Ok((c, escaped)),
+            })
+            .collect()
+    }
+
+    /// the produced symbols for the consumed `symbols`,
+    /// a group must be consumed on the same tape to write back its symbol
+    fn group_produce(
+        &self,
+        prod: &[GroupToken],
+        cons: &[GroupToken],
+        symbols: &[(char, bool)],
+    ) -> Result<Vec<(char, bool)>, SyntaxError> {
+        prod.iter()
+            .enumerate()
+            .map(|(i, token)| match (token, cons.get(i)) {
+                (GroupToken::Symbol(c, escaped), _) => Ok((*c, *escaped)),
+                (GroupToken::Group(name), Some(GroupToken::Group(consumed)))
+                    if name == consumed =>
+                {
+                    Ok(symbols[i])
+                }
+                (GroupToken::Group(name), _) => Err(SyntaxError::new(
+                    SyntaxErrorType::TransitionGroupNotValid,
+                    format!(
+                        "{} produces group `{{{name}}}` on tape {i}, \
+                         which is not consumed on the same tape",
+                        self.describe()
+                    ),
+                )),
+            })
+            .collect()
+    }
+
+    /// split `cons` or `prod` into symbols and references to groups like `{digit}`
+    fn group_tokens(
+        &self,
+        s: &str,
+        config: &PatternConfig,
+    ) -> Result<Vec<GroupToken>, SyntaxError> {
+        let (symbols, escaped) = self.get_symbols(s, config)?;
+        let error = |message: String| {
+            SyntaxError::new(
+                SyntaxErrorType::TransitionGroupNotValid,
+                format!("{} {message}", self.describe()),
+            )
+        };
+        let mut tokens = vec![];
+        let mut i = 0;
+        while i < symbols.len() {
+            if symbols[i] != '{' || escaped[i] {
+                tokens.push(GroupToken::Symbol(symbols[i], escaped[i]));
+                i += 1;
+                continue;
+            }
+            let end = (i..symbols.len())
+                .find(|&j| symbols[j] == '}' && !escaped[j])
+                .ok_or_else(|| error(format!("`{s}` has a `{{` without `}}`")))?;
+            let name = symbols[i + 1..end].iter().collect::<String>();
+            if !config.groups.contains_key(&name) {
+                return Err(error(format!("refers to group `{name}` not in the config")));
+            }
+            tokens.push(GroupToken::Group(name));
+            i = end + 1;
+        }
+        Ok(tokens)
+    }
+
+    /// refer to the next state by name if it is given by position,
+    /// `names` are the names of the states of the model in order.
+    /// An empty next state loops back to `from`, the state of the transition
+    pub(crate) fn resolve_next(&mut self, names: &[String], from: &str) -> Result<(), SyntaxError> {
+        if matches!(&self.next_state_name, StateRef::Name(name) if name.is_empty()) {
+            self.next_state_name = StateRef::Name(from.to_string());
+        }
+        if let StateRef::Index(i) = self.next_state_name {
+            let name = names.get(i).ok_or_else(|| {
+                SyntaxError::new(
+                    SyntaxErrorType::TransitionNextStateNotFound,
+                    format!(
+                        "{} next state `{i}` is not a position, the model has {} states",
+                        self.describe(),
+                        names.len()
+                    ),
+                )
+            })?;
+            self.next_state_name = StateRef::Name(name.clone());
+        }
+        Ok(())
+    }
+
+    /// the number of symbols in `cons`, escapes included
+    pub(crate) fn consume_len(&self) -> usize {
+        self.cons.chars().count()
+    }
+
     /// into transition with syntax check
-    pub fn into_transition(self, config: PatternConfig) -> Result<Transition, SyntaxError> {
-        let (consume, produce) = self.get_consume_produce()?;
-        let consume_pattern = config.parse(&consume);
-        let direction = self.get_direction()?;
+    pub fn into_transition(self, config: &PatternConfig) -> Result<Transition, SyntaxError> {
+        let regex = self.regex(config)?;
+        let (consume, consume_escaped) = match &regex {
+            Some((_, wildcard, _)) => (vec![*wildcard], vec![false]),
+            None => self.get_symbols(&self.cons, config)?,
+        };
+        let (mut produce, produce_escaped) = match &self.prod {
+            Some(prod) => self.get_symbols(prod, config)?,
+            None => (consume.clone(), consume_escaped.clone()),
+        };
+        // a wildcard produced for a regex keeps the symbol like the wildcard consumed
+        if let (Some((_, wildcard, _)), [c], [false]) = (&regex, &produce[..], &produce_escaped[..])
+        {
+            if config.is_wildcard(*c) {
+                produce = vec![*wildcard];
+            }
+        }
+        if consume.len() != produce.len() {
+            return Err(SyntaxError::new(
+                SyntaxErrorType::TransitionConsumeProduceNotMatch,
+                format!("{} consume and produce symbols not match", self.describe()),
+            ));
+        }
+        // a produced wildcard only means keeping the symbol the same wildcard matched,
+        // or any symbol with `produce_keeps`
+        let wildcard_write = (0..consume.len()).find(|&i| {
+            !config.produce_keeps
+                && !produce_escaped[i]
+                && config.is_wildcard(produce[i])
+                && (consume_escaped[i] || consume[i] != produce[i])
+        });
+        if let Some(i) = wildcard_write {
+            return Err(SyntaxError::new(
+                SyntaxErrorType::TransitionProduceNotValid,
+                format!(
+                    "{} produces wildcard `{}` for consumed `{}`, \
+                     a produced wildcard keeps the symbol only if the same wildcard is consumed",
+                    self.describe(),
+                    produce[i],
+                    consume[i]
+                ),
+            ));
+        }
+        // a read only tape may only get back the symbol read
+        let readonly_write = config.readonly_tapes.iter().find(|&&i| {
+            let same = consume.get(i) == produce.get(i)
+                && consume_escaped.get(i) == produce_escaped.get(i);
+            let keeps = config.produce_keeps
+                && produce_escaped.get(i) == Some(&false)
+                && produce.get(i).is_some_and(|c| config.is_wildcard(*c));
+            i < consume.len() && !same && !keeps
+        });
+        if let Some(i) = readonly_write {
+            return Err(SyntaxError::new(
+                SyntaxErrorType::TransitionWritesReadonlyTape,
+                format!(
+                    "{} writes `{}` on read only tape {i}, it must keep the symbol `{}`",
+                    self.describe(),
+                    produce[*i],
+                    consume[*i]
+                ),
+            ));
+        }
+        let (consume_regex, consume_pattern) = match regex {
+            Some((source, _, pattern)) => (Some(source), vec![pattern]),
+            None => (None, config.parse_escaped(&consume, &consume_escaped)),
+        };
+        let moves = self.moves(consume.len(), config)?;
+        let direction = self.get_direction(&moves, config)?;
         if direction.len() != consume.len() {
-            return Err(SyntaxError {
-                error_type: SyntaxErrorType::TransitionConsumeProduceNotMatch,
-                message: format!(
-                    "Transition `{}` -> `{}` consume do not match move direction `{}`",
-                    self.cons, self.prod, self.next_direction
+            return Err(SyntaxError::new(
+                SyntaxErrorType::TransitionConsumeProduceNotMatch,
+                format!(
+                    "{} consume do not match move direction `{moves}`",
+                    self.describe(),
                 ),
-            });
+            ));
+        }
+        if let Some(w) = self.weight.filter(|w| !w.is_finite() || *w < 0.0) {
+            return Err(SyntaxError::new(
+                SyntaxErrorType::TransitionWeightNotValid,
+                format!(
+                    "{} weight `{w}` is not a non-negative number",
+                    self.describe()
+                ),
+            ));
         }
         Ok(Transition {
             consume,
+            consume_escaped,
             consume_pattern,
+            consume_regex,
             produce,
+            produce_escaped,
             direction,
-            next_state_name: self.next_state_name,
+            next_state_name: match self.next_state_name {
+                StateRef::Name(name) => name,
+                StateRef::Index(i) => auto_name(i),
+            },
+            next_id: None,
+            weight: self.weight,
+            label: self.label,
+            note: self.note,
+            folded: vec![],
         })
     }
 
-    /// get move directions
-    fn get_direction(&self) -> Result<Vec<Direction>, SyntaxError> {
-        self.next_direction
+    /// the regex of `cons` between slashes with `regex_cons`, compiled,
+    /// and the wildcard consumed in its place
+    fn regex(&self, config: &PatternConfig) -> Result<Option<ConsRegex>, SyntaxError> {
+        let source = match self
+            .cons
+            .strip_prefix('/')
+            .and_then(|s| s.strip_suffix('/'))
+        {
+            Some(source) if config.regex_cons && !source.is_empty() => source,
+            _ => return Ok(None),
+        };
+        let error = |message: String| {
+            SyntaxError::new(
+                SyntaxErrorType::TransitionRegexNotValid,
+                format!("{} {message}", self.describe()),
+            )
+        };
+        let wildcard = config
+            .any
+            .or(config.some_wildcard)
+            .ok_or_else(|| error("is a regex, which needs `any` or `some` enabled".to_string()))?;
+        let pattern = compile_regex(source, error)?;
+        Ok(Some((source.to_string(), wildcard, pattern)))
+    }
+
+    /// describe the transition for messages
+    fn describe(&self) -> String {
+        let prod = self.prod.as_deref().unwrap_or(&self.cons);
+        describe(&self.cons, prod, self.label.as_deref())
+    }
+
+    /// get `move`, or the `default_move` of the config if omitted,
+    /// a single default direction is used for all `tapes`
+    fn moves(&self, tapes: usize, config: &PatternConfig) -> Result<String, SyntaxError> {
+        match (&self.next_direction, &config.default_move) {
+            (Some(moves), _) => Ok(moves.clone()),
+            (None, Some(moves)) if moves.chars().count() == 1 => Ok(moves.repeat(tapes)),
+            (None, Some(moves)) => Ok(moves.clone()),
+            (None, None) => Err(SyntaxError::new(
+                SyntaxErrorType::TransitionDirectionNotFound,
+                format!(
+                    "{} has no `move`, and no `default_move` is set",
+                    self.describe()
+                ),
+            )),
+        }
+    }
+
+    /// get move directions,
+    /// whitespace and `,` may separate the directions for readability
+    fn get_direction(
+        &self,
+        moves: &str,
+        config: &PatternConfig,
+    ) -> Result<Vec<Direction>, SyntaxError> {
+        let stay = config.stay.to_uppercase();
+        moves
             .to_uppercase()
             .chars()
+            .filter(|c| !c.is_whitespace() && *c != ',')
             .map(|c| match c {
                 'L' => Ok(Direction::Left),
                 'R' => Ok(Direction::Right),
-                'S' => Ok(Direction::Stay),
-                _ => Err(SyntaxError {
-                    error_type: SyntaxErrorType::TransitionDirectionNotFound,
-                    message: format!(
-                        "Transition `{}` -> `{}` direction `{c}` not found",
-                        self.cons, self.prod
-                    ),
-                }),
+                c if stay.contains(c) => Ok(Direction::Stay),
+                _ => Err(SyntaxError::new(
+                    SyntaxErrorType::TransitionDirectionNotFound,
+                    format!("{} direction `{c}` not found", self.describe()),
+                )),
             })
             .collect()
     }
 
-    /// get pair of consume and produce symbols
-    fn get_consume_produce(&self) -> Result<(Vec<char>, Vec<char>), SyntaxError> {
-        let consume = self.cons.chars().collect::<Vec<char>>();
-        let produce = self.prod.chars().collect::<Vec<char>>();
-        if consume.len() != produce.len() {
-            Err(SyntaxError {
-                error_type: SyntaxErrorType::TransitionConsumeProduceNotMatch,
-                message: format!(
-                    "Transition `{}` -> `{}` consume and produce symbols not match",
-                    self.cons, self.prod
-                ),
-            })
-        } else {
-            Ok((consume, produce))
-        }
+    /// get the symbols of `cons` or `prod` and which of them are escaped
+    fn get_symbols(
+        &self,
+        s: &str,
+        config: &PatternConfig,
+    ) -> Result<(Vec<char>, Vec<bool>), SyntaxError> {
+        config.split_escapes(s).ok_or_else(|| {
+            SyntaxError::new(
+                SyntaxErrorType::TransitionEscapeNotValid,
+                format!("{} `{s}` ends with a lone escape char", self.describe()),
+            )
+        })
     }
 
-    /// create serializable transition from transition
-    pub fn from_transition(transition: &Transition) -> Self {
-        // get the direction from direction
-        let next_direction = transition
-            .direction
-            .iter()
-            .map(|d| match d {
-                Direction::Left => 'L',
-                Direction::Right => 'R',
-                Direction::Stay => 'S',
-            })
-            .collect();
+    /// create serializable transition from transition,
+    /// escaping symbols with the config escape char
+    pub fn from_transition(transition: &Transition, config: &PatternConfig) -> Self {
+        // get the direction from direction, staying as the config spells it
+        let next_direction = Self::join_directions(&transition.direction, config);
         // get the next state name
         let next_state_name = transition.next_state_name.clone();
         Self {
-            cons: transition.consume.iter().collect(),
-            prod: transition.produce.iter().collect(),
-            next_direction,
-            next_state_name,
+            cons: match &transition.consume_regex {
+                Some(regex) => format!("/{regex}/"),
+                None => config.join_escapes(&transition.consume, &transition.consume_escaped),
+            },
+            prod: Some(config.join_escapes(&transition.produce, &transition.produce_escaped)),
+            next_direction: Some(next_direction),
+            next_state_name: StateRef::Name(next_state_name),
+            weight: transition.weight,
+            label: transition.label.clone(),
+            note: transition.note.clone(),
+            push: None,
+            pop: false,
         }
     }
 }
 
 /// the direction to move
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Direction {
     Left,
     Right,
     Stay,
 }
+
+/// `move` as a string of directions, or an array with one direction per tape
+mod moves {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Moves {
+        Packed(String),
+        Tokens(Vec<String>),
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<String>, D::Error> {
+        match Moves::deserialize(deserializer)? {
+            Moves::Packed(s) => Ok(Some(s)),
+            Moves::Tokens(tokens) => match tokens.iter().find(|t| t.trim().chars().count() != 1) {
+                Some(t) => Err(D::Error::custom(format!(
+                    "expected a single direction in `move`, found `{t}`"
+                ))),
+                None => Ok(Some(tokens.join(","))),
+            },
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! This module contains the warnings of the static checks on a machine.
+
+use std::fmt::{Display, Formatter};
+
+/// a suspicious but valid construct found by `Machine::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationWarning {
+    /// final states with outgoing transitions, sorted by name.
+    /// The machine keeps running after reaching them,
+    /// and only accepts if it halts there.
+    FinalStateHasTransitions(Vec<String>),
+    /// symbols written to a tape that no transition matches on that tape, sorted.
+    /// The machine can write them but never read them back
+    WriteOnlySymbols(Vec<char>),
+    /// transitions of one sided tapes moving a head left while it is surely at the start,
+    /// as `state: transition`, sorted. Taking them is always an error
+    MovesLeftOfBoundary(Vec<String>),
+    /// transitions consuming `some_wildcard` on a tape that is always blank in their state,
+    /// as `state: transition`, sorted. They never match, `any` was likely meant
+    SomeWildcardOnBlank(Vec<String>),
+}
+
+impl Display for ValidationWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationWarning::FinalStateHasTransitions(states) => write!(
+                f,
+                "Final states with outgoing transitions: `{}`",
+                states.join("`, `")
+            ),
+            ValidationWarning::WriteOnlySymbols(symbols) => write!(
+                f,
+                "Symbols written but never matched: `{}`",
+                symbols
+                    .iter()
+                    .map(char::to_string)
+                    .collect::<Vec<_>>()
+                    .join("`, `")
+            ),
+            ValidationWarning::MovesLeftOfBoundary(transitions) => write!(
+                f,
+                "Transitions moving left of the tape start: `{}`",
+                transitions.join("`, `")
+            ),
+            ValidationWarning::SomeWildcardOnBlank(transitions) => write!(
+                f,
+                "Transitions matching a symbol where the head is always blank: `{}`",
+                transitions.join("`, `")
+            ),
+        }
+    }
+}
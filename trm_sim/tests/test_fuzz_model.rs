@@ -0,0 +1,84 @@
+//! Property tests feeding arbitrary models to `Machine::new`,
+//! it should always return `Ok` or a `SyntaxError` and never panic.
+
+use proptest::prelude::*;
+use trm_sim::trm::Machine;
+
+/// steps to run a constructed machine, to also catch panics at run time
+const MAX_STEPS: usize = 64;
+
+/// run a machine for a bounded number of steps, ignoring the result
+fn run_bounded(machine: &mut Machine, input: &str) {
+    machine.input(input);
+    for _ in 0..MAX_STEPS {
+        match machine.run_once() {
+            Ok(false) => {}
+            _ => break,
+        }
+    }
+}
+
+/// strategy for one transition in toml syntax
+fn transition() -> impl Strategy<Value = String> {
+    (
+        "[01_*.a]{0,3}",
+        "[01_*.a]{0,3}",
+        "[LRSx ]{0,3}",
+        "q[0-3]",
+    )
+        .prop_map(|(cons, prod, mv, next)| {
+            format!(
+                "[[state.trans]]\ncons = \"{cons}\"\nprod = \"{prod}\"\nmove = \"{mv}\"\nnext = \"{next}\"\n"
+            )
+        })
+}
+
+/// strategy for one state with its transitions in toml syntax
+fn state() -> impl Strategy<Value = String> {
+    (
+        "q[0-3]",
+        any::<bool>(),
+        any::<bool>(),
+        prop::collection::vec(transition(), 0..4),
+    )
+        .prop_map(|(name, start, fin, trans)| {
+            format!(
+                "[[state]]\nname = \"{name}\"\nstart = {start}\nfinal = {fin}\n{}",
+                trans.concat()
+            )
+        })
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_strings_never_panic(model in any::<String>(), fmt in "json|toml|yaml|[a-z]{0,4}") {
+        if let Ok(mut machine) = Machine::new(&model, &fmt) {
+            run_bounded(&mut machine, "01");
+        }
+    }
+
+    #[test]
+    fn arbitrary_json_never_panic(model in r#"\{"states": \[[{}\[\]":,a-z0-9 ]{0,64}\]\}"#) {
+        if let Ok(mut machine) = Machine::new(&model, "json") {
+            run_bounded(&mut machine, "01");
+        }
+    }
+
+    #[test]
+    fn structured_toml_never_panic(
+        states in prop::collection::vec(state(), 0..5),
+        input in "[01a_]{0,6}",
+    ) {
+        if let Ok(mut machine) = Machine::new(&states.join("\n"), "toml") {
+            run_bounded(&mut machine, &input);
+        }
+    }
+}
+
+/// regression: a model without any state is an error, not a panic
+#[test]
+fn test_zero_states() {
+    assert!(Machine::new("", "toml").is_err());
+    assert!(Machine::new("{}", "json").is_err());
+    assert!(Machine::new(r#"{"states": []}"#, "json").is_err());
+}
@@ -0,0 +1,3770 @@
+//! Test usage of the machine api
+
+use trm_sim::trm::{
+    Difference, Direction, Machine, MachineModel, MachineRunningError, ModelLimits, PatternConfig,
+    RunOutcome, RunTally, StateSerde, SyntaxErrorType, Transition, TransitionSerde,
+    ValidationWarning, MODEL_VERSION,
+};
+
+/// flips every bit and halts on the first blank
+const BIT_FLIP: &str = r#"
+[[state]]
+name = "q0"
+start = true
+final = true
+
+[[state.trans]]
+cons = "0"
+prod = "1"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "1"
+prod = "0"
+move = "R"
+next = "q0"
+"#;
+
+/// test run statistics on the bit-flip machine
+#[test]
+fn test_run_stats() {
+    let mut machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    assert!(machine.stats().is_none());
+    machine.record_stats(true);
+    machine.input("1101");
+    assert!(machine.run().unwrap());
+
+    let stats = machine.stats().unwrap();
+    assert_eq!(stats.tapes.len(), 1);
+    assert_eq!(stats.tapes[0].head_moves, 4);
+    assert_eq!(stats.tapes[0].max_left, 0);
+    assert_eq!(stats.tapes[0].max_right, 4);
+    assert_eq!(stats.tapes[0].cells_written(), 4);
+    assert_eq!(stats.writes_per_symbol[&'0'], 3);
+    assert_eq!(stats.writes_per_symbol[&'1'], 1);
+
+    machine.reset();
+    assert_eq!(machine.stats().unwrap().tapes.len(), 0);
+    assert!(machine.stats().unwrap().writes_per_symbol.is_empty());
+
+    machine.input("0000");
+    machine.run().unwrap();
+    let writes = &machine.stats().unwrap().writes_per_symbol;
+    assert_eq!(writes.get(&'1'), Some(&4));
+    assert_eq!(writes.get(&'0'), None);
+}
+
+/// test a dangling next state is reported with the state it is taken from
+#[test]
+fn test_next_state_not_found() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "0_"
+prod = "1_"
+move = "RS"
+next = "nowhere"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("0");
+    let err = machine.run().err().unwrap();
+    assert!(matches!(
+        &err,
+        MachineRunningError::NextStateNotFound { from, missing, heads }
+            if from == "q0" && missing == "nowhere" && heads == &['0', '_']
+    ));
+    let message = err.to_string();
+    assert!(message.contains("`nowhere`"));
+    assert!(message.contains("`q0`"));
+    assert!(message.contains("`0_`"));
+}
+
+/// test transitions consuming different numbers of tapes are rejected
+#[test]
+fn test_tape_num_not_match() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+
+[[state.trans]]
+cons = "00"
+prod = "11"
+move = "RR"
+next = "q1"
+[[state.trans]]
+cons = "1"
+prod = "0"
+move = "R"
+next = "q1"
+
+[[state]]
+name = "q1"
+final = true
+"#;
+    let err = Machine::new(model, "toml").err().unwrap();
+    assert!(matches!(
+        err.error_type,
+        SyntaxErrorType::TransitionTapeNumberNotMatch
+    ));
+    assert!(err.message.contains("`1` -> `0`"));
+    assert!(err.message.contains("`q0`"));
+
+    let no_tape = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = ""
+prod = ""
+move = ""
+next = "q0"
+"#;
+    let err = Machine::new(no_tape, "toml").err().unwrap();
+    assert!(matches!(
+        err.error_type,
+        SyntaxErrorType::TransitionTapeNumberNotMatch
+    ));
+}
+
+/// test the tape number is taken from the transitions, so a copy machine fills both tapes
+#[test]
+fn test_tape_num_copy() {
+    let model = r#"
+[[state]]
+name = "copy"
+start = true
+[[state.trans]]
+cons = "0_"
+prod = "00"
+move = "RR"
+next = "copy"
+[[state.trans]]
+cons = "1_"
+prod = "11"
+move = "RR"
+next = "copy"
+[[state.trans]]
+cons = "__"
+prod = "__"
+move = "SS"
+next = "done"
+
+[[state]]
+name = "done"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("0110");
+    assert_eq!(machine.tapes().len(), 2);
+    assert!(machine.run().unwrap());
+    let id = machine.identifier();
+    for tape in &id.tape {
+        assert_eq!(tape.tape, "0110_");
+        assert_eq!(tape.head, 4);
+    }
+}
+
+/// test each tape is seeded with its own input, the missing ones blank
+#[test]
+fn test_input_tapes() {
+    // adds the bits of the second tape to the first, without carries
+    let model = r#"
+[[state]]
+name = "add"
+start = true
+final = true
+[[state.trans]]
+cons = "*0"
+prod = "*0"
+move = "RR"
+next = "add"
+[[state.trans]]
+cons = "01"
+prod = "11"
+move = "RR"
+next = "add"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input_tapes(&["101", "000"]).unwrap();
+    assert_eq!(machine.identifier().tape[0].tape, "101");
+    assert_eq!(machine.identifier().tape[1].tape, "000");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.steps(), 3);
+    assert_eq!(machine.identifier().tape[1].head, 3);
+
+    machine.reset();
+    machine.input_tapes(&["001", "010"]).unwrap();
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().tape[0].tape, "011_");
+
+    machine.reset();
+    machine.input_tapes(&["1"]).unwrap();
+    assert_eq!(machine.identifier().tape[1].tape, "_");
+    machine.reset();
+    let err = machine.input_tapes(&["1", "0", "1"]).err().unwrap();
+    assert!(matches!(
+        err,
+        MachineRunningError::InputTapeNumberNotMatch {
+            expected: 2,
+            found: 3
+        }
+    ));
+    assert!(machine.tapes().is_empty());
+}
+
+/// test models from a newer format version are rejected
+#[test]
+fn test_model_version() {
+    let model = format!("version = 1\n{BIT_FLIP}");
+    assert!(Machine::new(&model, "toml").is_ok());
+
+    let model = format!("version = 999\n{BIT_FLIP}");
+    let err = Machine::new(&model, "toml").err().unwrap();
+    assert!(matches!(
+        err.error_type,
+        SyntaxErrorType::VersionNotSupported(999)
+    ));
+    assert!(err.message.contains("999"));
+    assert!(err.message.contains(&MODEL_VERSION.to_string()));
+}
+
+/// test strict mode rejects misspelled keys ignored by lenient mode
+#[test]
+fn test_new_strict() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+finall = true
+
+[[state.trans]]
+cons = "0"
+prod = "1"
+move = "R"
+next = "q0"
+nextt = "q1"
+"#;
+    assert!(Machine::new(model, "toml").is_ok());
+    let err = Machine::new_strict(model, "toml").err().unwrap();
+    assert!(matches!(err.error_type, SyntaxErrorType::UnknownField(_)));
+    assert!(err.message.contains("finall"));
+    assert!(err.message.contains("nextt"));
+
+    let json = r#"{"states": [{"name": "q0", "start": true, "transitions": [
+        {"cons": "0", "prod": "1", "move": "R", "next": "q0", "conss": "1"}
+    ]}]}"#;
+    assert!(Machine::new(json, "json").is_ok());
+    assert!(Machine::new_strict(json, "json").is_err());
+    assert!(Machine::new_strict(BIT_FLIP, "toml").is_ok());
+}
+
+/// all strings over the alphabet up to the given length
+fn strings_up_to(alphabet: &[char], max_len: usize) -> Vec<String> {
+    let mut all = vec![String::new()];
+    let mut last = vec![String::new()];
+    for _ in 0..max_len {
+        last = last
+            .iter()
+            .flat_map(|s| alphabet.iter().map(move |c| format!("{s}{c}")))
+            .collect();
+        all.extend(last.iter().cloned());
+    }
+    all
+}
+
+/// test a right scanning machine extracts to an equivalent dfa
+#[test]
+fn test_as_dfa() {
+    // accepts strings over {a, b} ending with `b`
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "a"
+prod = "a"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "b"
+prod = "b"
+move = "R"
+next = "q1"
+
+[[state]]
+name = "q1"
+final = true
+[[state.trans]]
+cons = "a"
+prod = "a"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "R"
+next = "q1"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    let dfa = machine.as_dfa().unwrap();
+    assert_eq!(dfa.start_state(), "q0");
+    for input in strings_up_to(&['a', 'b', 'c'], 4) {
+        machine.reset();
+        machine.input(&input);
+        assert_eq!(dfa.accepts(&input), machine.run().unwrap(), "{input}");
+    }
+
+    let machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    assert!(machine.as_dfa().is_none());
+}
+
+/// test deserializer errors are kept as the error source
+#[test]
+fn test_syntax_error_source() {
+    use std::error::Error;
+
+    let err = Machine::new("[[state]\nname = 1", "toml").err().unwrap();
+    let source = err.source().unwrap();
+    assert!(source.downcast_ref::<toml::de::Error>().is_some());
+
+    let err = Machine::new("{\"states\": 1}", "json").err().unwrap();
+    let source = err.source().unwrap();
+    assert!(source.downcast_ref::<serde_json::Error>().is_some());
+
+    let err = Machine::new(BIT_FLIP, "txt").err().unwrap();
+    assert!(err.source().is_none());
+}
+
+/// test the start state is inferred when asked and unambiguous
+#[test]
+fn test_infer_start() {
+    let states = r#"
+[[state]]
+name = "q1"
+final = true
+
+[[state]]
+name = "q0"
+[[state.trans]]
+cons = "0"
+prod = "0"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "q1"
+"#;
+    let config = r#"
+[config]
+infer_start = true
+"#;
+    assert!(Machine::new(states, "toml").is_err());
+
+    let mut machine = Machine::new(&format!("{config}{states}"), "toml").unwrap();
+    machine.input("001");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().current_state, "q1");
+
+    let ambiguous = format!("{config}{states}\n[[state]]\nname = \"q2\"\n");
+    let err = Machine::new(&ambiguous, "toml").err().unwrap();
+    assert!(matches!(err.error_type, SyntaxErrorType::StartStateError));
+    assert!(err.message.contains("q2"));
+}
+
+/// test loop detection on a machine bouncing its head forever
+#[test]
+fn test_run_detect_loop() {
+    let model = r#"
+[[state]]
+name = "a"
+start = true
+[[state.trans]]
+cons = "."
+prod = "."
+move = "R"
+next = "b"
+
+[[state]]
+name = "b"
+[[state.trans]]
+cons = "."
+prod = "."
+move = "L"
+next = "a"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("0110");
+    assert_eq!(
+        machine.run_detect_loop(Some(1)).unwrap(),
+        RunOutcome::LoopDetected
+    );
+    machine.reset();
+    machine.input("0110");
+    assert_eq!(
+        machine.run_detect_loop(None).unwrap(),
+        RunOutcome::LoopDetected
+    );
+
+    let mut machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    machine.input("0110");
+    assert_eq!(
+        machine.run_detect_loop(Some(1)).unwrap(),
+        RunOutcome::Accepted
+    );
+    assert_eq!(machine.identifier().tape[0].tape, "1001_");
+
+    // every window of a uniform input looks the same, only the head tells the steps apart
+    machine.reset();
+    machine.input("0000");
+    assert_eq!(
+        machine.run_detect_loop(Some(1)).unwrap(),
+        RunOutcome::Accepted
+    );
+    assert_eq!(machine.identifier().tape[0].tape, "1111_");
+}
+
+/// test enumerating the accepted strings of an even number of zeros machine
+#[test]
+fn test_accepted_strings() {
+    let model = r#"
+[[state]]
+name = "even"
+start = true
+final = true
+[[state.trans]]
+cons = "0"
+prod = "0"
+move = "R"
+next = "odd"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "even"
+
+[[state]]
+name = "odd"
+[[state.trans]]
+cons = "0"
+prod = "0"
+move = "R"
+next = "even"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "odd"
+"#;
+    let machine = Machine::new(model, "toml").unwrap();
+    let accepted = machine.accepted_strings(&['1', '0', '1'], 3, 100);
+    assert_eq!(
+        accepted,
+        vec!["", "1", "00", "11", "001", "010", "100", "111"]
+    );
+    assert_eq!(machine.accepted_strings(&['0'], 3, 1), vec![""]);
+    assert_eq!(machine.accepted_strings(&['0'], 3, 2), vec!["", "00"]);
+}
+
+/// test the pretty view shows the state and the tape
+#[test]
+fn test_identifier_pretty() {
+    let mut machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    machine.input("01");
+    machine.run_once().unwrap();
+    let pretty = machine.identifier().to_string_pretty();
+    assert!(pretty.contains("State: q0"));
+    assert!(pretty.contains("Tape 0: 1[1]\n"));
+    assert_eq!(pretty.lines().filter(|l| l.starts_with("Tape")).count(), 1);
+}
+
+/// test probabilistic runs follow the weights and are reproducible
+#[test]
+fn test_run_probabilistic() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "S"
+next = "a"
+weight = 3.0
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "S"
+next = "b"
+
+[[state]]
+name = "a"
+final = true
+
+[[state]]
+name = "b"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    let mut choices = |seeds: std::ops::Range<u64>| {
+        seeds
+            .map(|seed| {
+                machine.reset();
+                machine.input("0");
+                machine.run_probabilistic(seed, 10).unwrap()
+            })
+            .collect::<Vec<_>>()
+    };
+    let first = choices(0..1000);
+    assert_eq!(first, choices(0..1000));
+    let accepted = first.iter().filter(|o| **o == RunOutcome::Accepted).count();
+    assert!((650..850).contains(&accepted), "{accepted}");
+    assert!(first.iter().all(|o| *o != RunOutcome::StepLimitReached));
+
+    let negative = model.replace("weight = 3.0", "weight = -1.0");
+    let err = Machine::new(&negative, "toml").err().unwrap();
+    assert!(matches!(
+        err.error_type,
+        SyntaxErrorType::TransitionWeightNotValid
+    ));
+}
+
+/// test a seeded probabilistic run takes the same steps again after a reset
+#[test]
+fn test_run_probabilistic_reset() {
+    // writes `a` or `b` at random over every `0`
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+final = true
+[[state.trans]]
+cons = "0"
+prod = "a"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "0"
+prod = "b"
+move = "R"
+next = "q0"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.record_choices(true);
+    let mut trace = |seed| {
+        machine.reset();
+        machine.input("0000000000");
+        let outcome = machine.run_probabilistic(seed, 100).unwrap();
+        assert_eq!(outcome, RunOutcome::Accepted);
+        let tape = machine.identifier().tape[0].tape.clone();
+        (machine.choices().to_vec(), tape)
+    };
+    let (choices, tape) = trace(7);
+    assert_eq!(choices.len(), 10);
+    assert_eq!((choices.clone(), tape.clone()), trace(7));
+    assert_ne!((choices, tape), trace(8));
+}
+
+/// test directions may be separated by whitespace and commas
+#[test]
+fn test_move_separators() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "*_"
+prod = "*_"
+move = "R, L"
+next = "q1"
+
+[[state]]
+name = "q1"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("0");
+    machine.run_once().unwrap();
+    let id = machine.identifier();
+    assert_eq!(id.tape[0].head, 1);
+    assert_eq!(id.tape[1].head, -1);
+}
+
+/// test `.` and `-` mean stay in `move` unless configured otherwise
+#[test]
+fn test_stay_symbols() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "."
+prod = "."
+move = "-"
+next = "q1"
+
+[[state]]
+name = "q1"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("01");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().tape[0].head, 0);
+    assert!(Machine::new(&model.replace("\"-\"", "\".\""), "toml").is_ok());
+
+    let only_s = format!("[config]\nstay = \"S\"\n{model}");
+    assert!(Machine::new(&only_s, "toml").is_err());
+    assert!(Machine::new(&only_s.replace("\"-\"", "\"s\""), "toml").is_ok());
+
+    // a saved model moves with the configured stay symbol and parses again
+    let only_dash = format!("[config]\nstay = \"-\"\n{model}");
+    let saved = Machine::new(&only_dash, "toml")
+        .unwrap()
+        .save("toml")
+        .unwrap();
+    assert!(saved.contains("move = \"-\""), "{saved}");
+    assert!(Machine::new(&saved, "toml").is_ok());
+}
+
+/// test final states that can keep running are reported
+#[test]
+fn test_validate_final_transitions() {
+    let machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    assert_eq!(
+        machine.validate(),
+        vec![ValidationWarning::FinalStateHasTransitions(vec![
+            "q0".to_string()
+        ])]
+    );
+
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "_"
+prod = "_"
+move = "S"
+next = "q1"
+
+[[state]]
+name = "q1"
+final = true
+"#;
+    let machine = Machine::new(model, "toml").unwrap();
+    assert!(machine.validate().is_empty());
+}
+
+/// test `*` is reported where the head is always blank, as it never matches there
+#[test]
+fn test_validate_some_wildcard_on_blank() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "*"
+prod = "_"
+move = "S"
+next = "erased"
+
+[[state]]
+name = "erased"
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "S"
+next = "done"
+
+[[state]]
+name = "done"
+final = true
+"#;
+    let machine = Machine::new(model, "toml").unwrap();
+    assert_eq!(
+        machine.validate(),
+        vec![ValidationWarning::SomeWildcardOnBlank(vec![
+            "erased: Transition `*` -> `*`".to_string()
+        ])]
+    );
+
+    // `.` matches the blank
+    let any = model.replace("cons = \"*\"\nprod = \"*\"", "cons = \".\"\nprod = \".\"");
+    assert!(Machine::new(&any, "toml").unwrap().validate().is_empty());
+    // the head may read a symbol after moving
+    let moved = model.replacen("move = \"S\"", "move = \"R\"", 1);
+    assert!(Machine::new(&moved, "toml").unwrap().validate().is_empty());
+}
+
+/// test symbols written but never matched are reported
+#[test]
+fn test_validate_write_only_symbols() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "0"
+prod = "X"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "_"
+prod = "_"
+move = "L"
+next = "q1"
+
+[[state]]
+name = "q1"
+final = true
+"#;
+    let machine = Machine::new(model, "toml").unwrap();
+    assert_eq!(
+        machine.validate(),
+        vec![ValidationWarning::WriteOnlySymbols(vec!['X'])]
+    );
+
+    // a wildcard reads the symbol back
+    let model = model.replace("cons = \"1\"\nprod = \"1\"", "cons = \"*\"\nprod = \"*\"");
+    let machine = Machine::new(&model, "toml").unwrap();
+    assert!(machine.validate().is_empty());
+}
+
+/// test transitions of a state that can match the same heads make a machine nondeterministic
+#[test]
+fn test_is_deterministic() {
+    assert!(Machine::new(BIT_FLIP, "toml").unwrap().is_deterministic());
+
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+final = true
+[[state.trans]]
+cons = "0_"
+prod = "0_"
+move = "RS"
+next = "q0"
+[[state.trans]]
+cons = "*_"
+prod = "*_"
+move = "RS"
+next = "q0"
+"#;
+    // `0_` and `*_` both match `0` over a blank
+    let machine = Machine::new(model, "toml").unwrap();
+    assert!(!machine.is_deterministic());
+
+    // the second tape tells them apart
+    let model = model.replace("\"*_\"", "\"*1\"");
+    let machine = Machine::new(&model, "toml").unwrap();
+    assert!(machine.is_deterministic());
+    let model = model
+        .replace("\"*1\"", "\".1\"")
+        .replace("\"0_\"", "\"0.\"");
+    let machine = Machine::new(&model, "toml").unwrap();
+    assert!(!machine.is_deterministic());
+}
+
+/// test `*` never matches a blank cell while `.` does
+#[test]
+fn test_some_and_any_on_blank() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "R"
+next = "q1"
+
+[[state]]
+name = "q1"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("0");
+    assert!(machine.run().unwrap());
+    machine.reset();
+    machine.input("");
+    assert!(!machine.run().unwrap());
+    assert_eq!(machine.identifier().current_state, "q0");
+
+    let model = model.replace("\"*\"", "\".\"");
+    let mut machine = Machine::new(&model, "toml").unwrap();
+    machine.input("");
+    assert!(machine.run().unwrap());
+    // a blank read by `.` is kept, nothing is written
+    assert_eq!(machine.identifier().tape[0].tape, "_");
+    assert_eq!(machine.tapes()[0].contents(), vec![None, None]);
+}
+
+/// test disabling `any` makes `.` a literal
+#[test]
+fn test_disable_any() {
+    let model = r#"
+[config]
+any = ""
+
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "."
+prod = "."
+move = "R"
+next = "q1"
+
+[[state]]
+name = "q1"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input(".");
+    assert!(machine.run().unwrap());
+    for input in ["0", ""] {
+        machine.reset();
+        machine.input(input);
+        assert!(!machine.run().unwrap(), "{input}");
+    }
+
+    let enabled = model.replace("any = \"\"", "");
+    let mut machine = Machine::new(&enabled, "toml").unwrap();
+    machine.input("0");
+    assert!(machine.run().unwrap());
+
+    let json = r#"{"config": {"some": null}}"#;
+    assert!(MachineModel::from_str(json, "json").is_ok());
+    let json = r#"{"config": {"some": "ab"}}"#;
+    let err = MachineModel::from_str(json, "json").err().unwrap();
+    let source = std::error::Error::source(&err).unwrap();
+    assert!(source.to_string().contains("single char"));
+}
+
+/// test errors on a labeled transition name the label
+#[test]
+fn test_transition_label() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+final = true
+[[state.trans]]
+label = "flip zero"
+cons = "0"
+prod = "1"
+move = "X"
+next = "q0"
+"#;
+    let err = Machine::new(model, "toml").err().unwrap();
+    assert!(matches!(
+        err.error_type,
+        SyntaxErrorType::TransitionDirectionNotFound
+    ));
+    assert!(err.message.contains("`flip zero`"), "{}", err.message);
+
+    let machine = Machine::new(&model.replace("\"X\"", "\"R\""), "toml").unwrap();
+    let toml = toml::to_string(&machine.model()).unwrap();
+    assert!(toml.contains("label = \"flip zero\""));
+}
+
+/// test a machine without meta chars runs the same as with the pattern matcher
+#[test]
+fn test_literal_only_machine() {
+    // an unreachable state consuming `*` turns the literal matcher off
+    let patterned = format!(
+        "{BIT_FLIP}\n[[state]]\nname = \"dead\"\n[[state.trans]]\n\
+         cons = \"*\"\nprod = \"*\"\nmove = \"R\"\nnext = \"dead\"\n"
+    );
+    let mut literal = Machine::new(BIT_FLIP, "toml").unwrap();
+    let mut patterned = Machine::new(&patterned, "toml").unwrap();
+    for input in ["", "0", "1101", "10a1", "a"] {
+        literal.reset();
+        patterned.reset();
+        literal.input(input);
+        patterned.input(input);
+        assert_eq!(literal.run().unwrap(), patterned.run().unwrap());
+        let (l, p) = (literal.identifier(), patterned.identifier());
+        assert_eq!(l.current_state, p.current_state);
+        assert_eq!(l.tape[0].tape, p.tape[0].tape, "{input}");
+        assert_eq!(l.tape[0].head, p.tape[0].head, "{input}");
+    }
+}
+
+/// test the state and transition counts
+#[test]
+fn test_counts() {
+    let machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    assert_eq!(machine.state_count(), 1);
+    assert_eq!(machine.transition_count(), 2);
+}
+
+/// test every head of a three tape machine moves as its transition says
+#[test]
+fn test_multi_tape_moves() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "*__"
+prod = "*__"
+move = "RLS"
+next = "q1"
+
+[[state]]
+name = "q1"
+[[state.trans]]
+cons = "*__"
+prod = "*__"
+move = "SRL"
+next = "q2"
+
+[[state]]
+name = "q2"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("00");
+    let heads = |m: &Machine| {
+        m.identifier()
+            .tape
+            .iter()
+            .map(|t| t.head)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(heads(&machine), vec![0, 0, 0]);
+    machine.run_once().unwrap();
+    assert_eq!(heads(&machine), vec![1, -1, 0]);
+    machine.run_once().unwrap();
+    assert_eq!(heads(&machine), vec![1, 0, -1]);
+    assert!(machine.run_once().unwrap());
+    assert!(machine.is_final());
+}
+
+/// test escaped meta chars are literals in `cons` and `prod`
+#[test]
+fn test_escape_meta_chars() {
+    let model = r#"
+[config]
+escape = "\\"
+
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "\\*"
+prod = "\\_"
+move = "R"
+next = "q1"
+[[state.trans]]
+cons = "\\\\"
+prod = "\\*"
+move = "R"
+next = "q0"
+
+[[state]]
+name = "q1"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("*");
+    assert!(machine.run().unwrap());
+    for input in ["a", ""] {
+        machine.reset();
+        machine.input(input);
+        assert!(!machine.run().unwrap(), "{input}");
+    }
+    machine.reset();
+    machine.input("\\\\*");
+    assert!(machine.run().unwrap());
+    // the last `_` is the blank under the head, the one before is written
+    assert_eq!(machine.identifier().tape[0].tape, "**__");
+
+    let toml = toml::to_string(&machine.model()).unwrap();
+    assert!(toml.contains(r#"cons = '\*'"#), "{toml}");
+    assert!(Machine::new(&toml, "toml").is_ok());
+
+    let lone = model.replace(r#"cons = "\\*""#, r#"cons = "*\\""#);
+    let err = Machine::new(&lone, "toml").err().unwrap();
+    assert!(matches!(
+        err.error_type,
+        SyntaxErrorType::TransitionEscapeNotValid
+    ));
+}
+
+/// test an escape char that is also a meta char is rejected with the config
+#[test]
+fn test_escape_clashes_meta_char() {
+    for (config, clash) in [
+        ("escape = \"_\"", "empty"),
+        ("escape = \"*\"", "some"),
+        ("escape = \".\"", "any"),
+        ("escape = \"!\"\nany = \"!\"", "any"),
+    ] {
+        let model = format!("[config]\n{config}\n{BIT_FLIP}");
+        let err = Machine::new(&model, "toml").err().unwrap();
+        assert!(
+            matches!(err.error_type, SyntaxErrorType::ConfigEscapeNotValid),
+            "{config}"
+        );
+        assert!(
+            err.message.contains(&format!("`{clash}`")),
+            "{}",
+            err.message
+        );
+    }
+    // a disabled wildcard frees its char
+    let model = format!("[config]\nescape = \"*\"\nsome = \"\"\n{BIT_FLIP}");
+    assert!(Machine::new(&model, "toml").is_ok());
+}
+
+/// test sorted transitions make the dot export independent of declaration order
+#[test]
+fn test_sort_transitions() {
+    // the same machine with its two transitions swapped
+    let parts = BIT_FLIP.split("[[state.trans]]").collect::<Vec<_>>();
+    let swapped = [parts[0], parts[2], parts[1]].join("[[state.trans]]");
+    let config = "[config]\nsort_transitions = true\n";
+
+    let a = Machine::new(BIT_FLIP, "toml").unwrap();
+    let b = Machine::new(&swapped, "toml").unwrap();
+    assert_ne!(a.to_dot(), b.to_dot());
+
+    let a = Machine::new(&format!("{config}{BIT_FLIP}"), "toml").unwrap();
+    let b = Machine::new(&format!("{config}{swapped}"), "toml").unwrap();
+    assert_eq!(a.to_dot(), b.to_dot());
+    assert!(a.to_dot().contains("\"q0\" [shape=doublecircle];"));
+    assert!(a.to_dot().contains("\"q0\" -> \"q0\" [label=\"0/1,R\"];"));
+}
+
+/// test the dot export of a run marks exactly the edges taken, by step
+#[test]
+fn test_to_dot_with_run() {
+    let machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    let dot = machine.to_dot_with_run("101", 10);
+    assert!(dot.contains("\"q0\" -> \"q0\" [label=\"0/1,R (2)\", color=red];"));
+    assert!(dot.contains("\"q0\" -> \"q0\" [label=\"1/0,R (1, 3)\", color=red];"));
+
+    let dot = machine.to_dot_with_run("11", 10);
+    assert!(dot.contains("\"q0\" -> \"q0\" [label=\"0/1,R\"];"));
+    assert!(dot.contains("\"q0\" -> \"q0\" [label=\"1/0,R (1, 2)\", color=red];"));
+    assert_eq!(dot.matches("color=red").count(), 1);
+
+    // the run is limited, and leaves the machine untouched
+    let dot = machine.to_dot_with_run("11", 1);
+    assert!(dot.contains("[label=\"1/0,R (1)\", color=red];"));
+    assert_eq!(machine.to_dot_with_run("", 10), machine.to_dot());
+}
+
+/// test running off the end of the input rejects only with `reject_end_of_input`
+#[test]
+fn test_reject_end_of_input() {
+    // a recognizer of an even number of `a`s, without a blank handler
+    let even = r#"
+[[state]]
+name = "even"
+start = true
+final = true
+[[state.trans]]
+cons = "a"
+prod = "a"
+move = "R"
+next = "odd"
+
+[[state]]
+name = "odd"
+[[state.trans]]
+cons = "a"
+prod = "a"
+move = "R"
+next = "even"
+"#;
+    let strict = format!("[config]\nreject_end_of_input = true\n{even}");
+    let mut machine = Machine::new(even, "toml").unwrap();
+    machine.input("aa");
+    assert_eq!(machine.run_with_limit(10).unwrap(), RunOutcome::Accepted);
+
+    let mut machine = Machine::new(&strict, "toml").unwrap();
+    for input in ["", "a", "aa", "aaa"] {
+        machine.reset();
+        machine.input(input);
+        assert_eq!(machine.run_with_limit(10).unwrap(), RunOutcome::Rejected);
+    }
+    assert_eq!(
+        machine.explain_verdict(),
+        "rejected: halted in non-final state `odd` because no transition matched symbol `_` on tape 0"
+    );
+    machine.reset();
+    machine.input("aa");
+    assert!(!machine.run().unwrap());
+    assert_eq!(
+        machine.explain_verdict(),
+        "rejected: halted in final state `even` past the end of the input"
+    );
+    // halting inside the input is judged as usual
+    machine.reset();
+    machine.input("aab");
+    assert_eq!(machine.run_with_limit(10).unwrap(), RunOutcome::Accepted);
+
+    // a blank handler of `odd` works past the input, and accepts there
+    let handled = format!(
+        "{strict}[[state.trans]]\ncons = \"_\"\nprod = \"_\"\nmove = \"S\"\nnext = \"accept\"\n\n\
+         [[state]]\nname = \"accept\"\nfinal = true\n"
+    );
+    let mut machine = Machine::new(&handled, "toml").unwrap();
+    machine.input("a");
+    assert_eq!(machine.run_with_limit(10).unwrap(), RunOutcome::Accepted);
+    machine.reset();
+    machine.input("aa");
+    assert!(!machine.run().unwrap());
+}
+
+/// test the default machine accepts anything right away
+#[test]
+fn test_default_machine() {
+    let mut machine = Machine::default();
+    machine.input("0_1 abc");
+    assert!(machine.is_halted());
+    assert_eq!(machine.run_with_limit(0).unwrap(), RunOutcome::Accepted);
+    assert!(machine.last_transition().is_none());
+    assert_eq!(machine.identifier().current_state, "q0");
+    assert_eq!(machine.final_states(), ["q0"]);
+}
+
+/// test models with windows or mixed line endings keep clean state names
+#[test]
+fn test_crlf_line_endings() {
+    let crlf = BIT_FLIP.replace('\n', "\r\n");
+    // every other line ending made windows style
+    let mixed = BIT_FLIP
+        .lines()
+        .enumerate()
+        .map(|(i, line)| format!("{line}{}", if i % 2 == 0 { "\r\n" } else { "\n" }))
+        .collect::<String>();
+    let json = Machine::new(BIT_FLIP, "toml")
+        .unwrap()
+        .model()
+        .to_text("json")
+        .unwrap();
+    let json = json.replace('\n', "\r\n");
+    for (model, fmt) in [(&crlf, "toml"), (&mixed, "toml"), (&json, "json")] {
+        let mut machine = Machine::new(model, fmt).unwrap();
+        assert_eq!(machine.final_states(), ["q0"]);
+        machine.input("01");
+        assert!(machine.run().unwrap());
+        assert_eq!(machine.identifier().current_state, "q0");
+        assert_eq!(machine.identifier().tape[0].tape, "10_");
+    }
+}
+
+/// test the transitions leaving and entering the middle state of a chain
+#[test]
+fn test_incoming_outgoing() {
+    let model = r#"{"states": [
+        {"name": "a", "start": true, "transitions": [
+            {"cons": "0", "prod": "0", "move": "R", "next": "b"},
+            {"cons": "1", "prod": "1", "move": "R", "next": "a"}]},
+        {"name": "b", "transitions": [
+            {"cons": "0", "prod": "1", "move": "R", "next": "c"},
+            {"cons": "1", "prod": "0", "move": "L", "next": "b"}]},
+        {"name": "c", "final": true, "transitions": [
+            {"cons": "_", "prod": "_", "move": "L", "next": "b"}]}
+    ]}"#;
+    let machine = Machine::new(model, "json").unwrap();
+    let cons = |t: &Transition| t.consume.iter().collect::<String>();
+
+    let outgoing = machine.outgoing("b");
+    assert_eq!(outgoing.iter().map(cons).collect::<Vec<_>>(), ["0", "1"]);
+    assert_eq!(outgoing[0].next_state_name, "c");
+
+    let incoming = machine.incoming("b");
+    let incoming = incoming
+        .iter()
+        .map(|(from, t)| (*from, cons(t)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        incoming,
+        [("a", "0".into()), ("b", "1".into()), ("c", "_".into())]
+    );
+
+    assert!(machine.outgoing("d").is_empty());
+    assert!(machine.incoming("d").is_empty());
+    assert_eq!(machine.incoming("a").len(), 1);
+}
+
+/// test models larger than the limits are rejected before being built
+#[test]
+fn test_model_limits() {
+    let model = r#"{"states": [
+        {"name": "a", "start": true, "transitions": [
+            {"cons": "0", "prod": "0", "move": "R", "next": "b"}]},
+        {"name": "b", "transitions": [
+            {"cons": "0", "prod": "0", "move": "R", "next": "c"}]},
+        {"name": "c", "final": true}
+    ]}"#;
+    let limited = |limits| Machine::new_with_limits(model, "json", limits).map(|_| ());
+    let error = limited(ModelLimits {
+        max_states: 2,
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::ModelLimitExceeded
+    ));
+    assert_eq!(
+        error.message,
+        "model has 3 states, but at most 2 are allowed"
+    );
+
+    let error = limited(ModelLimits {
+        max_transitions: 1,
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert!(error.message.contains("2 transitions"));
+    let error = limited(ModelLimits {
+        max_consume_len: 1,
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert!(error.message.contains("2 consumed symbols"));
+
+    let exact = ModelLimits {
+        max_states: 3,
+        max_transitions: 2,
+        max_consume_len: 2,
+    };
+    assert!(limited(exact).is_ok());
+    assert!(limited(ModelLimits::default()).is_ok());
+}
+
+/// test a group counts as one transition per symbol against the limits
+#[test]
+fn test_model_limits_groups() {
+    let model = r#"
+[config]
+groups = { digit = "0123456789" }
+
+[[state]]
+name = "q0"
+start = true
+
+[[state.trans]]
+cons = "{digit}"
+prod = "{digit}"
+move = "R"
+next = "q1"
+
+[[state]]
+name = "q1"
+final = true
+"#;
+    let limited = |max_transitions| {
+        let limits = ModelLimits {
+            max_transitions,
+            ..Default::default()
+        };
+        Machine::new_with_limits(model, "toml", limits).map(|_| ())
+    };
+    let error = limited(9).unwrap_err();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::ModelLimitExceeded
+    ));
+    assert_eq!(
+        error.message,
+        "model has 10 transitions, but at most 9 are allowed"
+    );
+    assert!(limited(10).is_ok());
+    let limits = ModelLimits {
+        max_consume_len: 9,
+        ..Default::default()
+    };
+    let error = Machine::new_with_limits(model, "toml", limits)
+        .err()
+        .unwrap();
+    assert!(error.message.contains("10 consumed symbols"));
+}
+
+/// test `move` given as an array of directions round trips
+#[test]
+fn test_move_array() {
+    let model = |moves: &str| {
+        format!(
+            "[[state]]\nname = \"q0\"\nstart = true\n\
+             [[state.trans]]\ncons = \"0_\"\nprod = \"1_\"\nmove = {moves}\nnext = \"q0\"\n"
+        )
+    };
+    let machine = Machine::new(&model(r#"["R", "s"]"#), "toml").unwrap();
+    let direction = &machine.outgoing("q0")[0].direction;
+    assert_eq!(direction, &[Direction::Right, Direction::Stay]);
+
+    // simple moves serialize packed, and parse back the same
+    let text = machine.model().to_text("toml").unwrap();
+    assert!(text.contains("move = \"RS\""));
+    let again = Machine::new(&text, "toml").unwrap();
+    assert_eq!(&again.outgoing("q0")[0].direction, direction);
+    let packed = Machine::new(&model("\"RS\""), "toml").unwrap();
+    assert_eq!(&packed.outgoing("q0")[0].direction, direction);
+
+    let error = Machine::new(&model(r#"["R3", "S"]"#), "toml")
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::SyntaxNotValid(_)
+    ));
+}
+
+/// test transitions omitting `prod` and `move` keep the symbol and take the default move
+#[test]
+fn test_default_move() {
+    let short = BIT_FLIP.replace("move = \"R\"\n", "");
+    let short = format!(
+        "[config]\ndefault_move = \"r\"\n{short}[[state.trans]]\ncons = \"_\"\nnext = \"q0\"\n"
+    );
+    let full = format!(
+        "{BIT_FLIP}[[state.trans]]\ncons = \"_\"\nprod = \"_\"\nmove = \"R\"\nnext = \"q0\"\n"
+    );
+    let mut short = Machine::new(&short, "toml").unwrap();
+    let mut full = Machine::new(&full, "toml").unwrap();
+    assert_eq!(short.outgoing("q0")[2].direction, [Direction::Right]);
+    for machine in [&mut short, &mut full] {
+        machine.input("011");
+        assert_eq!(
+            machine.run_with_limit(5).unwrap(),
+            RunOutcome::StepLimitReached
+        );
+    }
+    let (short, full) = (short.identifier(), full.identifier());
+    assert_eq!(short.tape[0].tape, full.tape[0].tape);
+    assert_eq!(short.tape[0].head, full.tape[0].head);
+    assert_eq!(short.tape[0].tape, "100___");
+
+    // without a default, `move` is required
+    let error = Machine::new(&BIT_FLIP.replace("move = \"R\"\n", ""), "toml")
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::TransitionDirectionNotFound
+    ));
+}
+
+/// test with `produce_keeps` a produced wildcard keeps the cell of its tape
+#[test]
+fn test_produce_keeps() {
+    let model = r#"
+[config]
+produce_keeps = true
+
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "0*"
+prod = "*_"
+move = "RR"
+next = "q0"
+[[state.trans]]
+cons = "1*"
+prod = "._"
+move = "RR"
+next = "q0"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input_delimited("0101|abcd", '|').unwrap();
+    assert_eq!(machine.run_with_limit(10).unwrap(), RunOutcome::Rejected);
+    let id = machine.identifier();
+    assert_eq!(id.tape[0].tape, "0101_");
+    assert_eq!(id.tape[1].tape, "_");
+
+    // without it, the wildcard must be consumed on the same tape
+    let strict = model.replace("produce_keeps = true", "");
+    let error = Machine::new(&strict, "toml").err().unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::TransitionProduceNotValid
+    ));
+}
+
+/// test tallying the runs of every line of a reader
+#[test]
+fn test_run_lines() {
+    // rejects inputs with a `2`, and runs forever on a `3`
+    let model = format!(
+        "{BIT_FLIP}[[state.trans]]\ncons = \"3\"\nprod = \"3\"\nmove = \"S\"\nnext = \"q0\"\n\
+         [[state.trans]]\ncons = \"2\"\nprod = \"2\"\nmove = \"S\"\nnext = \"q1\"\n\
+         [[state]]\nname = \"q1\"\n"
+    );
+    let mut machine = Machine::new(&model, "toml").unwrap();
+    let lines = std::io::Cursor::new("01\n\n012\r\n2\n103\n11");
+    let tally = machine.run_lines(lines, 100).unwrap();
+    let expected = RunTally {
+        accepted: 3,
+        rejected: 2,
+        timed_out: 1,
+        errors: 0,
+    };
+    assert_eq!(tally, expected);
+    assert_eq!(machine.identifier().tape[0].tape, "00_");
+}
+
+/// test a run paused in a snapshot continues like a run in one go
+#[test]
+fn test_snapshot_resume() {
+    let mut once = Machine::new(BIT_FLIP, "toml").unwrap();
+    once.input("01101");
+    assert!(once.run().unwrap());
+
+    let snapshot = {
+        let mut machine = Machine::new(BIT_FLIP, "toml").unwrap();
+        machine.input("01101");
+        for _ in 0..3 {
+            assert!(!machine.run_once().unwrap());
+        }
+        machine.snapshot()
+    };
+    let mut resumed = Machine::resume(BIT_FLIP, "toml", &snapshot).unwrap();
+    assert_eq!(resumed.steps(), 3);
+    assert_eq!(resumed.identifier().tape[0].tape, "10001");
+    assert!(resumed.run().unwrap());
+    assert_eq!(resumed.steps(), once.steps());
+    assert!(resumed.identifier().compare(&once.identifier()).is_empty());
+
+    // a snapshot of another machine does not fit
+    let other = "[[state]]\nname = \"a\"\nstart = true\n";
+    let error = Machine::resume(other, "toml", &snapshot).err().unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::SnapshotNotMatch
+    ));
+    assert!(Machine::resume(BIT_FLIP, "toml", "{}").is_err());
+
+    // a head far outside its tape is not valid, instead of filling the tape up to it
+    let mut far = serde_json::from_str::<serde_json::Value>(&snapshot).unwrap();
+    far["tapes"][0]["head"] = (1u64 << 48).into();
+    let error = Machine::resume(BIT_FLIP, "toml", &far.to_string())
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::SyntaxNotValid(_)
+    ));
+}
+
+/// test quoted state flags are read as booleans, and other strings are explained
+#[test]
+fn test_quoted_flags() {
+    let quoted = BIT_FLIP
+        .replace("start = true", "start = \"true\"")
+        .replace("final = true", "final = \"True\"");
+    let mut machine = Machine::new(&quoted, "toml").unwrap();
+    machine.input("01");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.final_states(), ["q0"]);
+
+    let json = r#"{"states": [{"name": "q0", "start": "true", "final": "false"}]}"#;
+    let machine = Machine::new(json, "json").unwrap();
+    assert!(machine.final_states().is_empty());
+
+    let error = Machine::new(&BIT_FLIP.replace("start = true", "start = \"yes\""), "toml")
+        .err()
+        .unwrap();
+    assert!(
+        error.to_string().contains("found the string `yes`"),
+        "{error}"
+    );
+    let error = Machine::new(&BIT_FLIP.replace("start = true", "start = 1"), "toml")
+        .err()
+        .unwrap();
+    assert!(
+        error.to_string().contains("a boolean `true` or `false`"),
+        "{error}"
+    );
+}
+
+/// test the trace of symbols read follows the input of the bit-flip machine
+#[test]
+fn test_head_symbol_trace() {
+    let mut machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    machine.input("0110");
+    machine.run().unwrap();
+    assert!(machine.head_symbol_trace(0).is_empty());
+
+    machine.record_stats(true);
+    machine.reset();
+    machine.input("0110");
+    machine.run().unwrap();
+    let trace = machine.head_symbol_trace(0);
+    assert_eq!(trace, "0110".chars().map(Some).collect::<Vec<_>>());
+    assert!(machine.head_symbol_trace(1).is_empty());
+}
+
+/// test unnamed states are named by position, and transitions may refer to them by position
+#[test]
+fn test_unnamed_states() {
+    let model = r#"{"states": [
+        {"start": true, "transitions": [
+            {"cons": "0", "prod": "1", "move": "R", "next": 0},
+            {"cons": "1", "prod": "1", "move": "R", "next": 1}]},
+        {"transitions": [
+            {"cons": "0", "prod": "0", "move": "R", "next": "done"}]},
+        {"name": "done", "final": true, "transitions": [
+            {"cons": "1", "prod": "1", "move": "L", "next": 0}]}
+    ]}"#;
+    let mut machine = Machine::new(model, "json").unwrap();
+    assert_eq!(
+        machine.describe(),
+        "States: `done`, `s0`, `s1`\nStart: `s0`\nFinal: `done`\n"
+    );
+    machine.input("0100");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().current_state, "done");
+    assert_eq!(machine.identifier().tape[0].tape, "1100");
+    assert_eq!(machine.incoming("s0").len(), 2);
+
+    // the model names every state and transition target again
+    let text = machine.model().to_text("json").unwrap();
+    assert!(text.contains("\"name\": \"s1\""));
+    assert!(Machine::new(&text, "json").is_ok());
+
+    let error = Machine::new(&model.replace("\"next\": 1", "\"next\": 3"), "json")
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::TransitionNextStateNotFound
+    ));
+
+    // a given name may not take the name of an unnamed state
+    let error = Machine::new(&model.replace("\"done\"", "\"s1\""), "json")
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::StateNameDuplicated
+    ));
+    assert_eq!(
+        error.message,
+        "state `s1` is defined twice, the unnamed state at position 1 is named `s1`"
+    );
+}
+
+/// test two states of the same name are rejected
+#[test]
+fn test_state_name_duplicated() {
+    let model = r#"{"states": [
+        {"name": "a", "start": true, "transitions": [
+            {"cons": "0", "prod": "0", "move": "R", "next": "a"}]},
+        {"name": "a", "final": true}
+    ]}"#;
+    let error = Machine::new(model, "json").err().unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::StateNameDuplicated
+    ));
+    assert_eq!(error.message, "state `a` is defined twice");
+}
+
+/// test a read only tape can be read, and transitions writing it are rejected
+#[test]
+fn test_readonly_tapes() {
+    // copies the oracle on the second tape to the first
+    let model = r#"
+[config]
+readonly_tapes = [1]
+
+[[state]]
+name = "q0"
+start = true
+final = true
+[[state.trans]]
+cons = "_0"
+prod = "00"
+move = "RR"
+next = "q0"
+[[state.trans]]
+cons = "_1"
+prod = "11"
+move = "RR"
+next = "q0"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input_delimited("|101", '|').unwrap();
+    assert!(machine.run().unwrap());
+    let id = machine.identifier();
+    assert_eq!(id.tape[0].tape, "101_");
+    assert_eq!(id.tape[1].tape, "101_");
+
+    let error = Machine::new(&model.replace("prod = \"11\"", "prod = \"10\""), "toml")
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::TransitionWritesReadonlyTape
+    ));
+    assert!(error.message.contains("writes `0` on read only tape 1"));
+    // the first tape is written, so it cannot be read only
+    assert!(Machine::new(&model.replace("[1]", "[0]"), "toml").is_err());
+    assert!(Machine::new(&model.replace("[1]", "[2]"), "toml").is_ok());
+}
+
+/// test a symbol group in `cons` matches any symbol of the group
+#[test]
+fn test_symbol_group_matches_any_digit() {
+    let model = r#"
+[config]
+groups = { digit = "0123456789" }
+
+[[state]]
+name = "q0"
+start = true
+
+[[state.trans]]
+cons = "{digit}"
+prod = "{digit}"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "_"
+prod = "_"
+move = "S"
+next = "q1"
+
+[[state]]
+name = "q1"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("0123456789");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().tape[0].tape, "0123456789_");
+    machine.reset();
+    machine.input("12a");
+    assert!(!machine.run().unwrap());
+
+    let unknown = model.replace("cons = \"{digit}\"", "cons = \"{letter}\"");
+    let error = Machine::new(&unknown, "toml").err().unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::TransitionGroupNotValid
+    ));
+    assert!(error.message.contains("group `letter` not in the config"));
+}
+
+/// test a transition consuming an empty group is an error, not dropped
+#[test]
+fn test_symbol_group_empty() {
+    let model = format!(
+        "[config]\ngroups = {{ d = \"\" }}\n{}",
+        BIT_FLIP.replacen("cons = \"0\"", "cons = \"{d}\"", 1)
+    );
+    let error = Machine::new(&model, "toml").err().unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::TransitionGroupNotValid
+    ));
+    assert!(
+        error.message.contains("group `d` is empty"),
+        "{}",
+        error.message
+    );
+    // an empty group no transition uses is fine
+    let unused = format!("[config]\ngroups = {{ d = \"\" }}\n{BIT_FLIP}");
+    assert!(Machine::new(&unused, "toml").is_ok());
+}
+
+/// test replaying the choices of an accepting nondeterministic run
+#[test]
+fn test_replay_choices_of_nondeterministic_run() {
+    // guesses where `11` starts
+    let model = r#"
+[[state]]
+name = "scan"
+start = true
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "R"
+next = "scan"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "one"
+
+[[state]]
+name = "one"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "found"
+
+[[state]]
+name = "found"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    // choices are only recorded when asked for
+    assert_eq!(machine.execute("00110", 100).unwrap(), RunOutcome::Accepted);
+    assert!(machine.choices().is_empty());
+    machine.record_choices(true);
+    assert_eq!(machine.execute("00110", 100).unwrap(), RunOutcome::Accepted);
+    let choices = machine.choices().to_vec();
+    assert_eq!(choices, [0, 0, 1, 0]);
+    let accepted = machine.identifier();
+
+    let mut replayed = Machine::new(model, "toml").unwrap();
+    assert_eq!(
+        replayed.replay("00110", &choices).unwrap(),
+        RunOutcome::Accepted
+    );
+    assert_eq!(replayed.identifier().current_state, accepted.current_state);
+    assert_eq!(replayed.identifier().tape[0].tape, accepted.tape[0].tape);
+    assert_eq!(replayed.identifier().tape[0].head, accepted.tape[0].head);
+    assert_eq!(replayed.steps(), 4);
+
+    // a prefix stops in the middle of the run
+    assert_eq!(
+        replayed.replay("00110", &choices[..2]).unwrap(),
+        RunOutcome::StepLimitReached
+    );
+    assert_eq!(replayed.identifier().tape[0].head, 2);
+    assert!(matches!(
+        replayed.replay("00110", &[1]),
+        Err(MachineRunningError::ReplayChoiceNotValid {
+            step: 0,
+            choice: 1,
+            ..
+        })
+    ));
+}
+
+/// test a plain run records no choices, so its memory does not grow with the steps
+#[test]
+fn test_record_choices() {
+    let mut machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    machine.input("0110");
+    assert!(machine.run().unwrap());
+    assert!(machine.choices().is_empty());
+
+    machine.record_choices(true);
+    machine.reset();
+    machine.input("0110");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.choices(), [0, 1, 1, 0]);
+    machine.record_choices(false);
+    assert!(machine.choices().is_empty());
+}
+
+/// test halting on the empty input within a cap
+#[test]
+fn test_halts_on_empty() {
+    let mut machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    assert!(machine.halts_on_empty(10));
+
+    // moves right over blanks forever
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "."
+prod = "."
+move = "R"
+next = "q0"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    assert!(!machine.halts_on_empty(10));
+
+    // one branch accepts at once, the other stays in `q0` forever
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "_"
+prod = "_"
+move = "S"
+next = "q0"
+[[state.trans]]
+cons = "_"
+prod = "_"
+move = "S"
+next = "done"
+
+[[state]]
+name = "done"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    assert_eq!(machine.execute("", 10).unwrap(), RunOutcome::Accepted);
+    assert!(!machine.halts_on_empty(10));
+    // every branch halts once the loop is gone
+    let halting = model.replacen("next = \"q0\"", "next = \"done\"", 1);
+    let mut machine = Machine::new(&halting, "toml").unwrap();
+    assert!(machine.halts_on_empty(10));
+}
+
+/// test a balanced parentheses recognizer pushing and popping the stack tape
+#[test]
+fn test_stack_tape_balanced_parentheses() {
+    let model = r#"
+[config]
+stack_tape = 1
+
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "(."
+move = "RS"
+push = "("
+next = "q0"
+[[state.trans]]
+cons = ")("
+move = "RS"
+pop = true
+next = "q0"
+[[state.trans]]
+cons = "__"
+move = "SS"
+next = "accept"
+
+[[state]]
+name = "accept"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    for (input, balanced) in [
+        ("", true),
+        ("()", true),
+        ("(()())", true),
+        ("(()", false),
+        ("())", false),
+        (")(", false),
+    ] {
+        machine.reset();
+        machine.input(input);
+        assert_eq!(machine.run().unwrap(), balanced, "{input}");
+    }
+
+    let unset = model.replace("stack_tape = 1", "");
+    let error = Machine::new(&unset, "toml").err().unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::TransitionStackEffectNotValid
+    ));
+
+    // a state of the model named like the state writing a pushed symbol
+    let taken = format!("{model}\n[[state]]\nname = \"q0~push(\"\n");
+    let error = Machine::new(&taken, "toml").err().unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::StateNameDuplicated
+    ));
+    assert!(error.message.contains("`q0~push(`"), "{}", error.message);
+}
+
+/// test the stack tape is found by tape, not by char, when `cons` refers to a group
+#[test]
+fn test_stack_tape_with_groups() {
+    // as many `x` as digits before them
+    let model = r#"
+[config]
+stack_tape = 1
+groups = { digit = "0123456789" }
+
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "{digit}."
+move = "RS"
+push = "d"
+next = "q0"
+[[state.trans]]
+cons = "xd"
+move = "RS"
+pop = true
+next = "q1"
+[[state.trans]]
+cons = "__"
+move = "SS"
+next = "accept"
+
+[[state]]
+name = "q1"
+[[state.trans]]
+cons = "xd"
+move = "RS"
+pop = true
+next = "q1"
+[[state.trans]]
+cons = "__"
+move = "SS"
+next = "accept"
+
+[[state]]
+name = "accept"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    for (input, accept) in [("", true), ("7x", true), ("042xxx", true), ("12x", false)] {
+        machine.reset();
+        machine.input(input);
+        assert_eq!(machine.run().unwrap(), accept, "{input}");
+    }
+    machine.reset();
+    machine.input("42");
+    machine.run().unwrap();
+    assert_eq!(machine.identifier().tape[0].tape, "42_");
+    assert_eq!(machine.identifier().tape[1].tape, "dd");
+}
+
+/// test a model file including a file of shared states
+#[test]
+fn test_from_path_merges_includes() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut machine = Machine::from_path(fixtures.join("include_base.toml")).unwrap();
+    assert_eq!(machine.state_count(), 3);
+    machine.input("0110");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().current_state, "done");
+    assert_eq!(machine.identifier().tape[0].tape, "1001_");
+
+    let error = Machine::from_path(fixtures.join("include_cycle_a.toml"))
+        .err()
+        .unwrap();
+    assert!(matches!(error.error_type, SyntaxErrorType::IncludeNotValid));
+    assert!(error.message.contains("cycle"));
+
+    let text = std::fs::read_to_string(fixtures.join("include_base.toml")).unwrap();
+    let error = Machine::new(&text, "toml").err().unwrap();
+    assert!(matches!(error.error_type, SyntaxErrorType::IncludeNotValid));
+}
+
+/// test exporting a run as one svg frame per configuration
+#[test]
+fn test_to_svg_frames() {
+    let mut machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    let frames = machine.to_svg_frames("0110", 100);
+    machine.input("0110");
+    machine.run().unwrap();
+    assert_eq!(frames.len(), machine.steps() + 1);
+    assert!(frames
+        .iter()
+        .all(|f| f.starts_with("<svg") && f.contains("State: q0")));
+    assert_eq!(frames[0].matches("<rect").count(), 5);
+    assert_eq!(frames[0].matches("fill=\"yellow\"").count(), 1);
+
+    assert_eq!(machine.to_svg_frames("0110", 2).len(), 3);
+}
+
+/// test a transition omitting `next` loops back to its own state
+#[test]
+fn test_omitted_next_loops_back() {
+    let model = r#"
+[[state]]
+name = "scan"
+start = true
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "R"
+[[state.trans]]
+cons = "_"
+prod = "_"
+move = "S"
+next = ""
+
+[[state]]
+name = "q1"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("01");
+    assert_eq!(
+        machine.run_with_limit(5).unwrap(),
+        RunOutcome::StepLimitReached
+    );
+    assert_eq!(machine.identifier().current_state, "scan");
+    assert_eq!(machine.identifier().tape[0].head, 2);
+    assert_eq!(machine.outgoing("scan")[0].next_state_name, "scan");
+}
+
+/// test a regex `cons` matches the symbols of its character class
+#[cfg(feature = "regex")]
+#[test]
+fn test_regex_cons_matches_digits() {
+    let model = r#"
+[config]
+regex_cons = true
+
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "/\\d/"
+prod = "*"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "_"
+prod = "_"
+move = "S"
+next = "q1"
+
+[[state]]
+name = "q1"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    assert!(machine.is_deterministic());
+    machine.input("0123456789");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().tape[0].tape, "0123456789_");
+    machine.reset();
+    machine.input("12a");
+    assert!(!machine.run().unwrap());
+
+    let saved = machine.save("toml").unwrap();
+    assert!(saved.contains(r"cons = '/\d/'"), "{saved}");
+    assert!(Machine::new(&saved, "toml").is_ok());
+
+    let error = Machine::new(&model.replace(r"\\d", "[0-"), "toml")
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::TransitionRegexNotValid
+    ));
+}
+
+/// test a regex `cons` is rejected without the `regex` feature
+#[cfg(not(feature = "regex"))]
+#[test]
+fn test_regex_cons_needs_feature() {
+    let model = r#"
+[config]
+regex_cons = true
+
+[[state]]
+name = "q0"
+start = true
+final = true
+[[state.trans]]
+cons = "/\\d/"
+prod = "*"
+move = "R"
+next = "q0"
+"#;
+    let error = Machine::new(model, "toml").err().unwrap();
+    assert!(matches!(
+        error.error_type,
+        SyntaxErrorType::TransitionRegexNotValid
+    ));
+    assert!(
+        error.message.ends_with("needs the `regex` feature"),
+        "{}",
+        error.message
+    );
+    // without `regex_cons` the slashes are plain symbols
+    let plain = model.replace("regex_cons = true", "");
+    assert!(Machine::new(&plain, "toml").is_err_and(|e| !e.message.contains("regex")));
+}
+
+/// test resetting the counter counts only the steps after it
+#[test]
+fn test_reset_counter() {
+    // scans right to the blank, then back left to the blank before the input
+    let model = r#"
+[[state]]
+name = "right"
+start = true
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "R"
+[[state.trans]]
+cons = "_"
+prod = "_"
+move = "L"
+next = "left"
+
+[[state]]
+name = "left"
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "L"
+[[state.trans]]
+cons = "_"
+prod = "_"
+move = "R"
+next = "done"
+
+[[state]]
+name = "done"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("abc");
+    while machine.identifier().current_state != "left" {
+        machine.run_once().unwrap();
+    }
+    assert_eq!(machine.steps(), 4);
+    machine.reset_counter();
+    assert_eq!(machine.identifier().tape[0].head, 2);
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.steps(), 4);
+    machine.reset();
+    assert_eq!(machine.steps(), 0);
+}
+
+/// test loading a gzip compressed model, the format is the extension before `.gz`
+#[cfg(feature = "gzip")]
+#[test]
+fn test_from_path_gzip() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut machine = Machine::from_path(fixtures.join("bit_flip.toml.gz")).unwrap();
+    machine.input("0110");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().tape[0].tape, "1001_");
+}
+
+/// test the identifier reports how far each head moved in the last step
+#[test]
+fn test_identifier_last_move() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "0_"
+prod = "0_"
+move = "RL"
+next = "q1"
+
+[[state]]
+name = "q1"
+[[state.trans]]
+cons = "1_"
+prod = "1_"
+move = "SR"
+next = "q1"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input_delimited("01,", ',').unwrap();
+    assert!(machine.identifier().last_move.is_empty());
+    machine.run_once().unwrap();
+    assert_eq!(machine.identifier().last_move, [1, -1]);
+    machine.run_once().unwrap();
+    assert_eq!(machine.identifier().last_move, [0, 1]);
+    machine.reset();
+    assert!(machine.identifier().last_move.is_empty());
+
+    // the accepting branch of a nondeterministic run keeps its last move
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "0"
+prod = "0"
+move = "R"
+next = "q1"
+[[state.trans]]
+cons = "0"
+prod = "0"
+move = "L"
+next = "q2"
+
+[[state]]
+name = "q1"
+
+[[state]]
+name = "q2"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    assert!(!machine.is_deterministic());
+    assert_eq!(machine.execute("0", 10).unwrap(), RunOutcome::Accepted);
+    assert_eq!(machine.identifier().last_move, [-1]);
+}
+
+/// test a one sided machine moving left from the start is warned about and fails
+#[test]
+fn test_one_sided_moves_left_of_start() {
+    let model = r#"
+[config]
+one_sided = true
+
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "S"
+next = "q1"
+
+[[state]]
+name = "q1"
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "L"
+next = "q1"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    assert_eq!(
+        machine.validate(),
+        [ValidationWarning::MovesLeftOfBoundary(vec![
+            "q1: Transition `*` -> `*`".to_string()
+        ])]
+    );
+    machine.input("0");
+    assert!(matches!(
+        machine.run(),
+        Err(MachineRunningError::LeftOfBoundary { tape: 0, .. })
+    ));
+
+    let right = model.replace("move = \"L\"", "move = \"R\"");
+    assert!(Machine::new(&right, "toml").unwrap().validate().is_empty());
+}
+
+/// accepts the input by trying every sequence of choices with `replay`,
+/// each from a fresh copy of the input
+fn accepts_by_replay(machine: &mut Machine, input: &str, choices: &mut Vec<usize>) -> bool {
+    match machine.replay(input, choices) {
+        Ok(RunOutcome::Accepted) => true,
+        Ok(RunOutcome::StepLimitReached) => {
+            let state = machine.identifier().current_state;
+            (0..machine.outgoing(&state).len()).any(|choice| {
+                choices.push(choice);
+                let accepted = accepts_by_replay(machine, input, choices);
+                choices.pop();
+                accepted
+            })
+        }
+        _ => false,
+    }
+}
+
+/// test execute with tapes shared between branches accepts as replaying every branch does
+#[test]
+fn test_execute_shared_tapes() {
+    // copies a guessed subsequence of the input to the second tape,
+    // accepts if it is `101`
+    let model = r#"
+[[state]]
+name = "pick"
+start = true
+[[state.trans]]
+cons = "0_"
+prod = "00"
+move = "RR"
+next = "pick"
+[[state.trans]]
+cons = "1_"
+prod = "11"
+move = "RR"
+next = "pick"
+[[state.trans]]
+cons = "*_"
+prod = "*_"
+move = "RS"
+next = "pick"
+[[state.trans]]
+cons = "__"
+prod = "__"
+move = "SL"
+next = "c1"
+
+[[state]]
+name = "c1"
+[[state.trans]]
+cons = "_1"
+prod = "_1"
+move = "SL"
+next = "c2"
+
+[[state]]
+name = "c2"
+[[state.trans]]
+cons = "_0"
+prod = "_0"
+move = "SL"
+next = "c3"
+
+[[state]]
+name = "c3"
+[[state.trans]]
+cons = "_1"
+prod = "_1"
+move = "SL"
+next = "c4"
+
+[[state]]
+name = "c4"
+[[state.trans]]
+cons = "__"
+prod = "__"
+move = "SS"
+next = "done"
+
+[[state]]
+name = "done"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.record_choices(true);
+    assert!(!machine.is_deterministic());
+    // every input of up to 5 bits
+    let inputs = (0..=5).flat_map(|len| {
+        (0..1u32 << len).map(move |n| {
+            (0..len)
+                .map(|i| if n >> i & 1 == 1 { '1' } else { '0' })
+                .collect::<String>()
+        })
+    });
+    for input in &inputs.collect::<Vec<_>>() {
+        let expected = accepts_by_replay(&mut machine, input, &mut vec![]);
+        let outcome = machine.execute(input, 100).unwrap();
+        assert_eq!(outcome == RunOutcome::Accepted, expected, "input `{input}`");
+        if expected {
+            assert_eq!(machine.identifier().tape[1].tape, "_101");
+            let choices = machine.choices().to_vec();
+            assert_eq!(
+                machine.replay(input, &choices).unwrap(),
+                RunOutcome::Accepted
+            );
+        }
+    }
+    assert_eq!(machine.execute("0110", 100).unwrap(), RunOutcome::Rejected);
+    assert_eq!(machine.execute("01101", 100).unwrap(), RunOutcome::Accepted);
+}
+
+/// test a state with its own blank reads and writes `#` as blank, while the model blank stays `_`
+#[test]
+fn test_state_blank() {
+    // appends `e`, then erases the first symbol
+    let model = r##"
+[[state]]
+name = "q0"
+start = true
+blank = "#"
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "#"
+prod = "e"
+move = "L"
+next = "back"
+
+[[state]]
+name = "back"
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "L"
+next = "back"
+[[state.trans]]
+cons = "_"
+prod = "_"
+move = "R"
+next = "erase"
+
+[[state]]
+name = "erase"
+blank = "#"
+[[state.trans]]
+cons = "0"
+prod = "#"
+move = "S"
+next = "done"
+
+[[state]]
+name = "done"
+final = true
+"##;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("01");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().tape[0].tape, "_1e");
+
+    let saved = machine.save("toml").unwrap();
+    assert!(saved.contains("blank = \"#\""));
+    let mut machine = Machine::new(&saved, "toml").unwrap();
+    machine.input("01");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().tape[0].tape, "_1e");
+
+    let err = Machine::new(&model.replace("blank = \"#\"", "blank = \"*\""), "toml")
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err.error_type,
+        SyntaxErrorType::StateBlankNotValid
+    ));
+}
+
+/// test a state blank tells the blank from a written `_` and an escaped blank symbol
+#[test]
+fn test_state_blank_literals() {
+    // writes `_` for `0` and `#` for `1`, then back from the end as `a` and `b`
+    let model = r##"
+[config]
+escape = "\\"
+
+[[state]]
+name = "q0"
+start = true
+blank = "#"
+[[state.trans]]
+cons = "0"
+prod = "_"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "1"
+prod = "\\#"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "#"
+prod = "#"
+move = "L"
+next = "q1"
+
+[[state]]
+name = "q1"
+blank = "#"
+[[state.trans]]
+cons = "_"
+prod = "a"
+move = "L"
+next = "q1"
+[[state.trans]]
+cons = "\\#"
+prod = "b"
+move = "L"
+next = "q1"
+[[state.trans]]
+cons = "#"
+prod = "#"
+move = "R"
+next = "done"
+
+[[state]]
+name = "done"
+final = true
+"##;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("0110");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().tape[0].tape, "abba");
+
+    let saved = machine.save("toml").unwrap();
+    let mut machine = Machine::new(&saved, "toml").unwrap();
+    machine.input("0110");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().tape[0].tape, "abba");
+}
+
+/// test the trivial machine reads the same from yaml as from toml
+#[test]
+fn test_new_yaml() {
+    let toml = include_str!("../../turing-programs/trivial_trm.toml");
+    let yaml = r#"
+state:
+  - name: A
+    start: true
+    transitions:
+      - { next: B, cons: b, prod: "_", move: L }
+      - { next: C, cons: "*", prod: "*", move: S }
+  - name: B
+    final: false
+  - name: C
+    final: true
+"#;
+    let mut from_toml = Machine::new(toml, "toml").unwrap();
+    for fmt in ["yaml", "yml"] {
+        let mut machine = Machine::new(yaml, fmt).unwrap();
+        assert_eq!(machine.to_dot(), from_toml.to_dot());
+        for (input, accept) in [("a", true), ("b", false), ("", false)] {
+            machine.reset();
+            machine.input(input);
+            assert_eq!(machine.run().unwrap(), accept, "{input}");
+            from_toml.reset();
+            from_toml.input(input);
+            assert_eq!(from_toml.run().unwrap(), accept, "{input}");
+        }
+        // saved as yaml it reads back the same
+        let saved = machine.save(fmt).unwrap();
+        let reread = Machine::new(&saved, fmt).unwrap();
+        assert_eq!(reread.to_dot(), from_toml.to_dot(), "{saved}");
+    }
+
+    let err = Machine::new("state: [", "yaml").err().unwrap();
+    assert!(matches!(err.error_type, SyntaxErrorType::SyntaxNotValid(_)));
+    assert!(Machine::new_strict("state: []\nstats: []\n", "yaml").is_err());
+}
+
+/// test the identifier followed step by step equals freezing the tapes again
+#[test]
+fn test_identifier_cached() {
+    // erases the first symbol, appends `x`, then writes `y` left of the erased cell
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "*"
+prod = "_"
+move = "R"
+next = "q1"
+
+[[state]]
+name = "q1"
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "R"
+next = "q1"
+[[state.trans]]
+cons = "_"
+prod = "x"
+move = "L"
+next = "q2"
+
+[[state]]
+name = "q2"
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "L"
+next = "q2"
+[[state.trans]]
+cons = "_"
+prod = "_"
+move = "L"
+next = "q3"
+
+[[state]]
+name = "q3"
+[[state.trans]]
+cons = "_"
+prod = "y"
+move = "R"
+next = "q4"
+
+[[state]]
+name = "q4"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    for every in [1, 3] {
+        machine.reset();
+        machine.input("abc");
+        let mut steps = 0;
+        while !machine.run_once().unwrap() {
+            steps += 1;
+            if steps % every == 0 {
+                let fresh = machine
+                    .tapes()
+                    .iter()
+                    .map(|t| t.freeze('_'))
+                    .collect::<Vec<_>>();
+                assert_eq!(machine.identifier().tape, fresh, "step {steps}");
+            }
+        }
+        assert_eq!(machine.identifier().tape[0], machine.tapes()[0].freeze('_'));
+        assert_eq!(machine.identifier().tape[0].tape, "y_bcx");
+    }
+    machine.trim_tapes();
+    assert_eq!(machine.identifier().tape[0], machine.tapes()[0].freeze('_'));
+}
+
+/// test a template is instantiated once per value, accepting a symbol repeated
+#[test]
+fn test_state_template() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "a"
+next = "seen_a"
+move = "R"
+[[state.trans]]
+cons = "b"
+next = "seen_b"
+move = "R"
+
+[[state]]
+name = "done"
+final = true
+
+[[template]]
+param = "c"
+values = ["a", "b"]
+[[template.state]]
+name = "seen_{c}"
+[[template.state.trans]]
+cons = "{c}"
+prod = "{c}"
+move = "R"
+next = "done"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    assert_eq!(machine.state_count(), 4);
+    assert_eq!(machine.outgoing("seen_a")[0].consume, ['a']);
+    assert_eq!(machine.outgoing("seen_b")[0].consume, ['b']);
+    for (input, accept) in [("aa", true), ("bb", true), ("ab", false), ("ba", false)] {
+        machine.reset();
+        machine.input(input);
+        assert_eq!(machine.run().unwrap(), accept, "{input}");
+    }
+    let saved = machine.save("toml").unwrap();
+    assert!(!saved.contains("template"));
+    assert_eq!(Machine::new(&saved, "toml").unwrap().state_count(), 4);
+
+    let twice = model.replace(r#"values = ["a", "b"]"#, r#"values = ["a", "a"]"#);
+    let err = Machine::new(&twice, "toml").err().unwrap();
+    assert!(matches!(err.error_type, SyntaxErrorType::TemplateNotValid));
+    assert!(err.message.contains("seen_a"));
+
+    // `{c}` would also be a group reference in `cons`
+    let grouped = format!("[config]\ngroups = {{ c = \"ab\" }}\n{model}");
+    let err = Machine::new(&grouped, "toml").err().unwrap();
+    assert!(matches!(err.error_type, SyntaxErrorType::TemplateNotValid));
+    assert_eq!(
+        err.message,
+        "template parameter `{c}` is also a symbol group"
+    );
+
+    // the instances are counted before they are made
+    let many = model.replace(r#"values = ["a", "b"]"#, r#"values = ["a", "b", "c"]"#);
+    let limited = |max_states| {
+        let limits = ModelLimits {
+            max_states,
+            ..Default::default()
+        };
+        Machine::new_with_limits(&many, "toml", limits).map(|_| ())
+    };
+    let err = limited(4).unwrap_err();
+    assert!(matches!(
+        err.error_type,
+        SyntaxErrorType::ModelLimitExceeded
+    ));
+    assert_eq!(
+        err.message,
+        "templates make 5 states, but at most 4 are allowed"
+    );
+    assert!(limited(5).is_ok());
+}
+
+/// test machines of the same states and transitions declared in another order are equal
+#[test]
+fn test_structural_eq() {
+    let machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    let reordered = r#"
+[[state]]
+name = "q0"
+final = true
+start = true
+
+[[state.trans]]
+cons = "1"
+prod = "0"
+move = "R"
+next = "q0"
+[[state.trans]]
+next = "q0"
+move = "R"
+prod = "1"
+cons = "0"
+"#;
+    let mut other = Machine::new(reordered, "toml").unwrap();
+    assert!(machine.structural_eq(&other));
+    other.input("0110");
+    other.run().unwrap();
+    assert!(machine.structural_eq(&other));
+    assert!(other.structural_eq(&machine));
+
+    let flips_to_two = Machine::new(&BIT_FLIP.replace("prod = \"1\"", "prod = \"2\""), "toml");
+    assert!(!machine.structural_eq(&flips_to_two.unwrap()));
+    let not_final = Machine::new(&BIT_FLIP.replace("final = true", ""), "toml").unwrap();
+    assert!(!machine.structural_eq(&not_final));
+    let with_state = format!("{BIT_FLIP}\n[[state]]\nname = \"q1\"\n");
+    assert!(!machine.structural_eq(&Machine::new(&with_state, "toml").unwrap()));
+}
+
+/// test trimming tapes after erasing a long input keeps the identifier
+#[test]
+fn test_trim_tapes() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+final = true
+[[state.trans]]
+cons = "*"
+prod = "_"
+move = "R"
+next = "q0"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input(&"01".repeat(100));
+    assert!(machine.run().unwrap());
+    let id = machine.identifier();
+    assert_eq!(machine.tapes()[0].contents().len(), 201);
+
+    machine.trim_tapes();
+    assert_eq!(machine.tapes()[0].contents().len(), 1);
+    let trimmed = machine.identifier();
+    assert_eq!(trimmed.tape[0].tape, id.tape[0].tape);
+    assert_eq!(trimmed.tape[0].head, id.tape[0].head);
+    assert_eq!(trimmed.tape[0].range, id.tape[0].range);
+}
+
+/// test trimming keeps an escaped `_` written at the end, so it still matches after
+#[test]
+fn test_trim_tapes_escaped_empty() {
+    let model = r#"
+[config]
+escape = "\\"
+
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "*"
+prod = "\\_"
+move = "L"
+next = "q1"
+
+[[state]]
+name = "q1"
+[[state.trans]]
+cons = "_"
+prod = "_"
+move = "R"
+next = "q2"
+
+[[state]]
+name = "q2"
+[[state.trans]]
+cons = "\\_"
+prod = "\\_"
+move = "S"
+next = "q3"
+
+[[state]]
+name = "q3"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("a");
+    assert!(!machine.run_once().unwrap());
+    let id = machine.identifier();
+    machine.trim_tapes();
+    assert_eq!(machine.tapes()[0].contents(), vec![None, Some('_')]);
+    assert_eq!(machine.identifier().tape, id.tape);
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().current_state, "q3");
+}
+
+/// test checking examples reports only the wrong expectations
+#[test]
+fn test_check_examples() {
+    let machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    assert!(machine
+        .check_examples(
+            &[("01", RunOutcome::Accepted), ("", RunOutcome::Accepted)],
+            10
+        )
+        .is_ok());
+
+    let examples = [
+        ("01", RunOutcome::Accepted),
+        ("0a", RunOutcome::Rejected),
+        ("0110", RunOutcome::Accepted),
+        ("000", RunOutcome::Accepted),
+    ];
+    let mismatches = machine.check_examples(&examples, 3).err().unwrap();
+    assert_eq!(mismatches.len(), 2);
+    assert_eq!(mismatches[0].input, "0a");
+    assert_eq!(mismatches[0].expected, RunOutcome::Rejected);
+    assert_eq!(mismatches[0].actual, Some(RunOutcome::Accepted));
+    assert_eq!(mismatches[0].identifier.tape[0].tape, "1a");
+    assert_eq!(mismatches[1].input, "0110");
+    assert_eq!(mismatches[1].actual, Some(RunOutcome::StepLimitReached));
+}
+
+/// test tests declared in the model are kept and written back
+#[test]
+fn test_model_tests() {
+    let model = format!(
+        "{BIT_FLIP}\n[[tests]]\ninput = \"01\"\naccept = true\n\
+         [[tests]]\ninput = \"2\"\naccept = false\n"
+    );
+    let machine = Machine::new(&model, "toml").unwrap();
+    assert_eq!(machine.tests().len(), 2);
+    assert_eq!(machine.tests()[1].input, "2");
+    assert!(!machine.tests()[1].accept);
+
+    let toml = toml::to_string(&machine.model()).unwrap();
+    let reloaded = Machine::new(&toml, "toml").unwrap();
+    assert_eq!(reloaded.tests(), machine.tests());
+    assert!(Machine::new(BIT_FLIP, "toml").unwrap().tests().is_empty());
+}
+
+/// test producing a wildcard not matched by the same wildcard is rejected
+#[test]
+fn test_produce_wildcard() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+final = true
+[[state.trans]]
+cons = "0*"
+prod = "**"
+move = "RR"
+next = "q0"
+"#;
+    let err = Machine::new(model, "toml").err().unwrap();
+    assert!(matches!(
+        err.error_type,
+        SyntaxErrorType::TransitionProduceNotValid
+    ));
+    assert!(
+        err.message.contains("wildcard `*` for consumed `0`"),
+        "{}",
+        err.message
+    );
+
+    assert!(Machine::new(&model.replace("\"0*\"", "\"**\""), "toml").is_ok());
+    assert!(Machine::new(&model.replace("\"0*\"", "\".*\""), "toml").is_err());
+    let escaped = format!(
+        "[config]\nescape = \"!\"\n{}",
+        model.replace("\"**\"", "\"!**\"")
+    );
+    assert!(Machine::new(&escaped, "toml").is_ok());
+}
+
+/// test one delimited input seeds every tape
+#[test]
+fn test_input_delimited() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+final = true
+[[state.trans]]
+cons = "a_"
+prod = "a_"
+move = "SS"
+next = "q0"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    assert_eq!(machine.tape_count(), 2);
+    machine.input_delimited("abc|def", '|').unwrap();
+    let id = machine.identifier();
+    assert_eq!(id.tape.len(), 2);
+    assert_eq!(id.tape[0].tape, "abc");
+    assert_eq!(id.tape[1].tape, "def");
+    assert!(id.tape.iter().all(|t| t.head == 0));
+
+    machine.reset();
+    let err = machine.input_delimited("abc|def|g", '|').err().unwrap();
+    assert!(matches!(
+        err,
+        MachineRunningError::InputTapeNumberNotMatch {
+            expected: 2,
+            found: 3
+        }
+    ));
+    assert!(machine.identifier().tape.is_empty());
+}
+
+/// test building machines from parsed values
+#[test]
+fn test_model_from_value() {
+    let transitions = ["0", "1"]
+        .iter()
+        .zip(["1", "0"])
+        .map(|(cons, prod)| {
+            serde_json::json!({"cons": cons, "prod": prod, "move": "R", "next": "q0"})
+        })
+        .collect::<Vec<_>>();
+    let value = serde_json::json!({
+        "states": [{"name": "q0", "start": true, "final": true, "transitions": transitions}],
+    });
+    let model = MachineModel::from_json_value(value).unwrap();
+    let mut machine = Machine::from_model(model).unwrap();
+    machine.input("0110");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().tape[0].tape, "1001_");
+
+    let value = BIT_FLIP.parse::<toml::Value>().unwrap();
+    let model = MachineModel::from_toml_value(value).unwrap();
+    assert_eq!(Machine::from_model(model).unwrap().transition_count(), 2);
+
+    let err = MachineModel::from_json_value(serde_json::json!({"states": 1}))
+        .err()
+        .unwrap();
+    assert!(matches!(err.error_type, SyntaxErrorType::SyntaxNotValid(_)));
+}
+
+/// test a model built in code makes a working machine
+#[test]
+fn test_model_in_code() {
+    let q0 = StateSerde::new(
+        "q0",
+        true,
+        false,
+        vec![
+            TransitionSerde::new("0", "0", "R", "q0"),
+            TransitionSerde::new("1", "1", "R", "q1"),
+        ],
+    );
+    let q1 = StateSerde::new("q1", false, true, vec![]);
+    let model = MachineModel::new(vec![q0, q1], PatternConfig::default());
+    let mut machine = Machine::from_model(model).unwrap();
+    machine.input("001");
+    assert!(machine.run().unwrap());
+    assert_eq!(machine.identifier().current_state, "q1");
+
+    let q0 = StateSerde::new("q0", false, false, vec![]);
+    let model = MachineModel::new(vec![q0], PatternConfig::default());
+    let err = Machine::from_model(model).err().unwrap();
+    assert!(matches!(err.error_type, SyntaxErrorType::StartStateError));
+}
+
+/// test a scan missing its end of input handling is stopped on blanks
+#[test]
+fn test_blank_scan_guard() {
+    // looks for a `1`, but keeps moving right after the input ends
+    let model = r#"
+[[state]]
+name = "scan"
+start = true
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "S"
+next = "found"
+[[state.trans]]
+cons = "."
+prod = "."
+move = "R"
+next = "scan"
+
+[[state]]
+name = "found"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("001");
+    assert_eq!(
+        machine.run_guard_blank_scan(5).unwrap(),
+        RunOutcome::Accepted
+    );
+
+    machine.reset();
+    machine.input("000");
+    assert_eq!(
+        machine.run_guard_blank_scan(5).unwrap(),
+        RunOutcome::BlankScanLoop
+    );
+    // three input cells, then six blank steps
+    assert_eq!(machine.identifier().tape[0].head, 9);
+
+    // writing on the blanks is not a blank scan
+    let fill = r#"
+[[state]]
+name = "a"
+start = true
+[[state.trans]]
+cons = "_"
+prod = "x"
+move = "R"
+next = "b"
+
+[[state]]
+name = "b"
+[[state.trans]]
+cons = "_"
+prod = "x"
+move = "R"
+next = "c"
+
+[[state]]
+name = "c"
+final = true
+"#;
+    let mut machine = Machine::new(fill, "toml").unwrap();
+    machine.input("");
+    assert_eq!(
+        machine.run_guard_blank_scan(1).unwrap(),
+        RunOutcome::Accepted
+    );
+    let mut machine = Machine::new(&fill.replace("\"x\"", "\"_\""), "toml").unwrap();
+    machine.input("");
+    assert_eq!(
+        machine.run_guard_blank_scan(1).unwrap(),
+        RunOutcome::BlankScanLoop
+    );
+}
+
+/// test a model without start and final flags explains both
+#[test]
+fn test_no_start_no_final() {
+    let model = BIT_FLIP
+        .replace("start = true\n", "")
+        .replace("final = true\n", "");
+    let err = Machine::new(&model, "toml").err().unwrap();
+    assert!(matches!(err.error_type, SyntaxErrorType::StartStateError));
+    assert!(err.message.contains("`start = true`"), "{}", err.message);
+    assert!(err.message.contains("`final = true`"), "{}", err.message);
+
+    let model = BIT_FLIP.replace("start = true\n", "");
+    let err = Machine::new(&model, "toml").err().unwrap();
+    assert!(!err.message.contains("final"), "{}", err.message);
+}
+
+/// test input placed left of the origin
+#[test]
+fn test_input_at() {
+    let mut machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    machine.input_at(-3, "abc");
+    let tape = &machine.tapes()[0];
+    assert_eq!(tape.get(-3), Some('a'));
+    assert_eq!(tape.get(-1), Some('c'));
+    assert_eq!(tape.head(), 0);
+    assert_eq!(tape.read(), None);
+    assert_eq!(machine.identifier().tape[0].range, -3..1);
+}
+
+/// test the jump table of a literal one tape machine picks the first declared transition
+#[test]
+fn test_jump_table_machine() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "b"
+prod = "x"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "a"
+prod = "y"
+move = "R"
+next = "q1"
+[[state.trans]]
+cons = "b"
+prod = "z"
+move = "R"
+next = "q1"
+
+[[state]]
+name = "q1"
+final = true
+[[state.trans]]
+cons = "a"
+prod = "a"
+move = "L"
+next = "q0"
+"#;
+    // an unreachable wildcard makes the same machine use the patterns
+    let patterned = format!("{model}\n[[state]]\nname = \"dead\"\n[[state.trans]]\ncons = \"*\"\nprod = \"*\"\nmove = \"R\"\nnext = \"dead\"\n");
+    let mut table = Machine::new(model, "toml").unwrap();
+    let mut patterned = Machine::new(&patterned, "toml").unwrap();
+    for input in strings_up_to(&['a', 'b', 'c'], 4) {
+        table.reset();
+        patterned.reset();
+        table.input(&input);
+        patterned.input(&input);
+        assert_eq!(
+            table.run_with_limit(50).unwrap(),
+            patterned.run_with_limit(50).unwrap(),
+            "{input}"
+        );
+        let (t, p) = (table.identifier(), patterned.identifier());
+        assert_eq!(t.current_state, p.current_state, "{input}");
+        assert_eq!(t.tape[0].tape, p.tape[0].tape, "{input}");
+    }
+}
+
+/// test comparing a machine with a smaller equivalent one and a broken one
+#[test]
+fn test_equivalent_up_to() {
+    // accepts inputs with an even number of zeros, keeping a redundant copy of `even`
+    let model = r#"
+[[state]]
+name = "even"
+start = true
+final = true
+[[state.trans]]
+cons = "0"
+prod = "0"
+move = "R"
+next = "odd"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "even2"
+
+[[state]]
+name = "even2"
+final = true
+[[state.trans]]
+cons = "0"
+prod = "0"
+move = "R"
+next = "odd"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "even"
+
+[[state]]
+name = "odd"
+[[state.trans]]
+cons = "0"
+prod = "0"
+move = "R"
+next = "even"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "odd"
+"#;
+    let minimized = r#"
+[[state]]
+name = "even"
+start = true
+final = true
+[[state.trans]]
+cons = "0"
+prod = "0"
+move = "R"
+next = "odd"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "even"
+
+[[state]]
+name = "odd"
+[[state.trans]]
+cons = "0"
+prod = "0"
+move = "R"
+next = "even"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "odd"
+"#;
+    let machine = Machine::new(model, "toml").unwrap();
+    let mut other = Machine::new(minimized, "toml").unwrap();
+    assert!(machine
+        .equivalent_up_to(&mut other, &['0', '1'], 5, 100)
+        .is_ok());
+
+    // `odd` no longer skips ones
+    let broken = minimized.replace(
+        "prod = \"1\"\nmove = \"R\"\nnext = \"odd\"",
+        "prod = \"1\"\nmove = \"R\"\nnext = \"even\"",
+    );
+    let mut other = Machine::new(&broken, "toml").unwrap();
+    let counterexample = machine
+        .equivalent_up_to(&mut other, &['0', '1'], 5, 100)
+        .err()
+        .unwrap();
+    assert_eq!(counterexample.input, "01");
+    assert_eq!(counterexample.this, Some(RunOutcome::Rejected));
+    assert_eq!(counterexample.other, Some(RunOutcome::Accepted));
+}
+
+/// test execute follows every branch of a nondeterministic machine
+#[test]
+fn test_execute() {
+    let mut machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    assert!(machine.is_deterministic());
+    assert_eq!(machine.execute("0110", 100).unwrap(), RunOutcome::Accepted);
+    assert_eq!(machine.identifier().tape[0].tape, "1001_");
+
+    // guesses where `11` starts, the first matching transition never guesses
+    let model = r#"
+[[state]]
+name = "scan"
+start = true
+[[state.trans]]
+cons = "*"
+prod = "*"
+move = "R"
+next = "scan"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "one"
+
+[[state]]
+name = "one"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "found"
+
+[[state]]
+name = "found"
+final = true
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    assert!(!machine.is_deterministic());
+    machine.input("0110");
+    assert_eq!(machine.run_with_limit(100).unwrap(), RunOutcome::Rejected);
+
+    assert_eq!(machine.execute("0110", 100).unwrap(), RunOutcome::Accepted);
+    assert_eq!(machine.identifier().current_state, "found");
+    assert_eq!(machine.identifier().tape[0].head, 3);
+    assert_eq!(machine.execute("0101", 100).unwrap(), RunOutcome::Rejected);
+    assert_eq!(
+        machine.execute("0110", 2).unwrap(),
+        RunOutcome::StepLimitReached
+    );
+    assert_eq!(machine.execute("0110", 3).unwrap(), RunOutcome::Accepted);
+}
+
+/// test running stops once the second tape contains the needle
+#[test]
+fn test_run_until_tape_contains() {
+    // writes an `x` on the second tape for every input symbol
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+final = true
+[[state.trans]]
+cons = "*_"
+prod = "*x"
+move = "RR"
+next = "q0"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    machine.input("abcd");
+    assert!(machine.run_until_tape_contains(1, "xxx", 100).unwrap());
+    assert_eq!(machine.identifier().tape[1].tape, "xxx_");
+    assert_eq!(machine.identifier().tape[0].head, 3);
+
+    // already there before the first step
+    assert!(machine.run_until_tape_contains(1, "xx", 100).unwrap());
+    assert_eq!(machine.identifier().tape[0].head, 3);
+
+    assert!(!machine.run_until_tape_contains(1, "xxxxx", 100).unwrap());
+    assert!(machine.is_final());
+    assert!(!machine.run_until_tape_contains(2, "", 100).unwrap());
+
+    machine.reset();
+    machine.input("abcd");
+    assert!(!machine.run_until_tape_contains(1, "xxx", 2).unwrap());
+    assert_eq!(machine.identifier().tape[1].tape, "xx_");
+}
+
+/// test comparing identifiers reports the first differing cell of a tape
+#[test]
+fn test_identifier_compare() {
+    let mut machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    machine.input("0110");
+    machine.run().unwrap();
+    let expected = machine.identifier();
+    assert!(machine.identifier().compare(&expected).is_empty());
+
+    machine.reset();
+    machine.input("0100");
+    machine.run().unwrap();
+    let actual = machine.identifier();
+    assert_eq!(
+        actual.compare(&expected),
+        vec![Difference::Cell {
+            tape: 0,
+            index: 2,
+            expected: Some('0'),
+            actual: Some('1'),
+        }]
+    );
+    assert_eq!(
+        actual.compare(&expected)[0].to_string(),
+        "Tape 0 has `1` at 2, expected `0`"
+    );
+
+    machine.reset();
+    machine.input("01");
+    machine.run().unwrap();
+    let differences = machine.identifier().compare(&expected);
+    assert_eq!(
+        differences,
+        vec![
+            Difference::Cell {
+                tape: 0,
+                index: 2,
+                expected: Some('0'),
+                actual: Some('_'),
+            },
+            Difference::Head {
+                tape: 0,
+                expected: 4,
+                actual: 2,
+            },
+        ]
+    );
+}
+
+/// test final states are listed in the same sorted order every time
+#[test]
+fn test_final_states_order() {
+    let names = ["q3", "q1", "q4", "q0", "q2"];
+    let model = names
+        .iter()
+        .map(|n| {
+            format!(
+                "[[state]]\nname = \"{n}\"\nstart = {}\nfinal = true\n",
+                *n == "q3"
+            )
+        })
+        .collect::<String>();
+    let machine = Machine::new(&model, "toml").unwrap();
+    assert_eq!(machine.final_states(), vec!["q0", "q1", "q2", "q3", "q4"]);
+    let description = machine.describe();
+    assert!(description.contains("Final: `q0`, `q1`, `q2`, `q3`, `q4`"));
+    for _ in 0..10 {
+        let machine = Machine::new(&model, "toml").unwrap();
+        assert_eq!(machine.describe(), description);
+    }
+
+    let toml = toml::to_string(&machine.model()).unwrap();
+    let positions = machine
+        .final_states()
+        .iter()
+        .map(|n| toml.find(&format!("name = \"{n}\"")).unwrap())
+        .collect::<Vec<_>>();
+    assert!(positions.windows(2).all(|w| w[0] < w[1]));
+}
+
+/// test a short transition leaves the trailing tapes untouched with `pad_tapes`
+#[test]
+fn test_pad_tapes() {
+    // only the transition of `q1`, never taken, consumes all three tapes
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "0"
+prod = "1"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "_"
+prod = "_"
+move = "S"
+next = "q1"
+
+[[state]]
+name = "q1"
+final = true
+[[state.trans]]
+cons = "0ab"
+prod = "0ab"
+move = "SSS"
+next = "q1"
+"#;
+    let err = Machine::new(model, "toml").err().unwrap();
+    assert!(matches!(
+        err.error_type,
+        SyntaxErrorType::TransitionTapeNumberNotMatch
+    ));
+
+    let padded = format!("[config]\npad_tapes = true\n{model}");
+    let mut machine = Machine::new(&padded, "toml").unwrap();
+    assert_eq!(machine.tape_count(), 3);
+    machine.input_delimited("00|x|y", '|').unwrap();
+    assert!(machine.run().unwrap());
+    let id = machine.identifier();
+    assert_eq!(id.tape[0].tape, "11_");
+    assert_eq!((id.tape[1].tape.as_str(), id.tape[1].head), ("x", 0));
+    assert_eq!((id.tape[2].tape.as_str(), id.tape[2].head), ("y", 0));
+
+    let disabled = format!("[config]\npad_tapes = true\nany = \"\"\n{model}");
+    let err = Machine::new(&disabled, "toml").err().unwrap();
+    assert!(err.message.contains("`any`"));
+}
+
+/// test halting accepts only in a final state, and a final state does not stop the run
+#[test]
+fn test_halt_outcome() {
+    // `q1` is final but moves on over a `1`, `dead` is not final
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "0"
+prod = "0"
+move = "R"
+next = "q1"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "dead"
+
+[[state]]
+name = "q1"
+final = true
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "q0"
+
+[[state]]
+name = "dead"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    let mut outcome = |input: &str| {
+        machine.reset();
+        machine.input(input);
+        assert!(!machine.is_halted() || input.is_empty());
+        let outcome = machine.run_with_limit(100).unwrap();
+        assert!(machine.is_halted());
+        (outcome, machine.identifier().current_state)
+    };
+    assert_eq!(outcome("0"), (RunOutcome::Accepted, "q1".to_string()));
+    assert_eq!(outcome("010"), (RunOutcome::Accepted, "q1".to_string()));
+    assert_eq!(outcome("01"), (RunOutcome::Rejected, "q0".to_string()));
+    assert_eq!(outcome("1"), (RunOutcome::Rejected, "dead".to_string()));
+    assert_eq!(outcome(""), (RunOutcome::Rejected, "q0".to_string()));
+}
+
+/// test random machines always build, run and are the same for the same seed
+#[test]
+fn test_random_machine() {
+    for seed in 0..50 {
+        let mut machine = Machine::random(seed, 5, &['a', 'b', '_', '*']);
+        assert_eq!(machine.state_count(), 5);
+        assert_eq!(machine.tape_count(), 1);
+        assert!(!machine.final_states().is_empty());
+        assert!(machine.is_deterministic());
+        for input in ["", "a", "abba", "bab"] {
+            machine.reset();
+            machine.input(input);
+            machine.run_with_limit(200).unwrap();
+        }
+        assert_eq!(
+            machine.to_dot(),
+            Machine::random(seed, 5, &['a', 'b', '_', '*']).to_dot()
+        );
+    }
+    assert_eq!(Machine::random(7, 0, &[]).state_count(), 1);
+}
+
+/// test a final state with `accept_if` only accepts when its condition holds
+#[test]
+fn test_accept_if() {
+    // erases `0`s and halts on the first `1` or blank
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+final = true
+accept_if = "tape0_empty"
+[[state.trans]]
+cons = "0"
+prod = "_"
+move = "R"
+next = "q0"
+"#;
+    let mut machine = Machine::new(model, "toml").unwrap();
+    for (input, accept) in [("", true), ("000", true), ("001", false), ("1", false)] {
+        machine.reset();
+        machine.input(input);
+        assert_eq!(machine.run().unwrap(), accept, "{input}");
+        assert!(machine.is_final());
+    }
+    machine.reset();
+    machine.input("01");
+    assert_eq!(machine.run_with_limit(10).unwrap(), RunOutcome::Rejected);
+    assert!(machine.as_dfa().is_none());
+
+    let toml = toml::to_string(&machine.model()).unwrap();
+    assert!(toml.contains("accept_if = \"tape0_empty\""));
+
+    let at_origin = model.replace("tape0_empty", "head0_at_origin");
+    let mut machine = Machine::new(&at_origin, "toml").unwrap();
+    machine.input("1");
+    assert!(machine.run().unwrap());
+    machine.reset();
+    machine.input("01");
+    assert!(!machine.run().unwrap());
+
+    let err = Machine::new(&model.replace("tape0_empty", "tape_empty"), "toml")
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err.error_type,
+        SyntaxErrorType::StateAcceptConditionNotValid
+    ));
+    assert!(err.message.contains("`tape_empty`"));
+}
+
+/// test literal transitions with the same effect are folded into one class
+#[test]
+fn test_fold_transitions() {
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+[[state.trans]]
+cons = "0"
+prod = "0"
+move = "R"
+next = "q1"
+[[state.trans]]
+cons = "a"
+prod = "b"
+move = "R"
+next = "q1"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "q1"
+[[state.trans]]
+cons = "2"
+prod = "2"
+move = "R"
+next = "q0"
+
+[[state]]
+name = "q1"
+final = true
+[[state.trans]]
+cons = "0"
+prod = "x"
+move = "L"
+next = "q0"
+[[state.trans]]
+cons = "*"
+prod = "x"
+move = "L"
+next = "q0"
+[[state.trans]]
+cons = "1"
+prod = "x"
+move = "L"
+next = "q0"
+"#;
+    let original = Machine::new(model, "toml").unwrap();
+    let mut folded = original.clone();
+    // only `q0` folds, `*` of `q1` also matches `0` and `1`
+    assert_eq!(folded.fold_transitions(), 1);
+    assert_eq!(folded.transition_count(), original.transition_count() - 1);
+    assert!(folded
+        .to_dot()
+        .contains("\"q0\" -> \"q1\" [label=\"[01]/0,R\"];"));
+    let toml = toml::to_string(&folded.model()).unwrap();
+    let reloaded = Machine::new(&toml, "toml").unwrap();
+    assert_eq!(reloaded.transition_count(), original.transition_count());
+
+    let mut original = original;
+    for input in strings_up_to(&['0', '1', '2', 'a'], 4) {
+        original.reset();
+        folded.reset();
+        original.input(&input);
+        folded.input(&input);
+        assert_eq!(
+            original.run_with_limit(50).unwrap(),
+            folded.run_with_limit(50).unwrap(),
+            "{input}"
+        );
+        assert_eq!(
+            original.identifier().compare(&folded.identifier()),
+            vec![],
+            "{input}"
+        );
+    }
+    assert_eq!(folded.fold_transitions(), 0);
+}
+
+/// test saving an unchanged preserved model gives back the text byte for byte
+#[test]
+fn test_save_preserving() {
+    let model = format!("# flips bits\n{BIT_FLIP}\n\n# the end\n");
+    let machine = Machine::new_preserving(&model, "toml").unwrap();
+    assert_eq!(machine.save("toml").unwrap(), model);
+
+    // another format or a plain machine is serialized again
+    let json = machine.save("json").unwrap();
+    assert!(!json.contains("flips bits"));
+    assert_eq!(Machine::new(&json, "json").unwrap().transition_count(), 2);
+    let plain = Machine::new(&model, "toml").unwrap().save("toml").unwrap();
+    assert!(!plain.contains("flips bits"));
+    assert!(Machine::new(&plain, "toml").is_ok());
+
+    // a changed model is serialized again
+    let model = r#"
+[[state]]
+name = "q0"
+start = true
+final = true
+[[state.trans]]
+cons = "0"
+prod = "0"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "a"
+prod = "b"
+move = "R"
+next = "q0"
+[[state.trans]]
+cons = "1"
+prod = "1"
+move = "R"
+next = "q0"
+"#;
+    let mut machine = Machine::new_preserving(model, "toml").unwrap();
+    assert_eq!(machine.save("toml").unwrap(), model);
+    assert_eq!(machine.fold_transitions(), 1);
+    assert_ne!(machine.save("toml").unwrap(), model);
+    assert!(machine.save("xml").is_err());
+}
+
+/// test a paced run calls back after every step, at about the given rate
+#[test]
+fn test_run_paced() {
+    let mut machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    machine.input("0110");
+    let mut heads = vec![];
+    let outcome = machine
+        .run_paced(f64::INFINITY, 100, |id| heads.push(id.tape[0].head))
+        .unwrap();
+    assert_eq!(outcome, RunOutcome::Accepted);
+    assert_eq!(heads, vec![1, 2, 3, 4]);
+
+    machine.reset();
+    machine.input("0110");
+    let mut calls = 0;
+    let start = std::time::Instant::now();
+    let outcome = machine.run_paced(200.0, 3, |_| calls += 1).unwrap();
+    assert_eq!(outcome, RunOutcome::StepLimitReached);
+    assert_eq!(calls, 3);
+    // three steps timed at 0, 5 and 10 ms
+    assert!(start.elapsed() >= std::time::Duration::from_millis(10));
+
+    machine.reset();
+    machine.input("0110");
+    let mut calls = 0;
+    machine.run_paced(0.0, 100, |_| calls += 1).unwrap();
+    assert_eq!(calls, 4);
+}
+
+/// test a model nested under `machine` reads like the flat one
+#[test]
+fn test_nested_machine_table() {
+    let flat = Machine::new(BIT_FLIP, "toml").unwrap();
+    let nested = BIT_FLIP
+        .replace("[[state]]", "[[machine.state]]")
+        .replace("[[state.trans]]", "[[machine.state.trans]]");
+    let machine = Machine::new_strict(&nested, "toml").unwrap();
+    assert_eq!(
+        toml::to_string(&machine.model()).unwrap(),
+        toml::to_string(&flat.model()).unwrap()
+    );
+
+    let json = format!("{{\"machine\": {}}}", flat.save("json").unwrap());
+    let machine = Machine::new(&json, "json").unwrap();
+    assert_eq!(machine.to_dot(), flat.to_dot());
+
+    let yaml = flat.save("yaml").unwrap().replace('\n', "\n  ");
+    let yaml = format!("machine:\n  {yaml}");
+    let machine = Machine::new_strict(&yaml, "yaml").unwrap();
+    assert_eq!(machine.to_dot(), flat.to_dot());
+
+    // only a lone `machine` key is unwrapped
+    let model = format!("{nested}\n[config]\nempty = \"_\"\n");
+    assert!(Machine::new(&model, "toml").is_err());
+    let model = BIT_FLIP.replace("\"q0\"", "\"machine\"");
+    let mut machine = Machine::new(&model, "toml").unwrap();
+    machine.input("01");
+    assert!(machine.run().unwrap());
+}
+
+/// test the verdict explains where and why the machine halted
+#[test]
+fn test_explain_verdict() {
+    let mut machine = Machine::new(BIT_FLIP, "toml").unwrap();
+    machine.input("01");
+    assert_eq!(
+        machine.explain_verdict(),
+        "running: state `q0` has a transition matching the heads"
+    );
+    machine.run().unwrap();
+    assert_eq!(
+        machine.explain_verdict(),
+        "accepted: halted in final state `q0` with no matching transition"
+    );
+
+    let model = BIT_FLIP.replace("final = true\n", "");
+    let mut machine = Machine::new(&model, "toml").unwrap();
+    machine.input("012");
+    assert!(!machine.run().unwrap());
+    assert_eq!(
+        machine.explain_verdict(),
+        "rejected: halted in non-final state `q0` because no transition matched symbol `2` on tape 0"
+    );
+
+    let model = BIT_FLIP.replace(
+        "final = true\n",
+        "final = true\naccept_if = \"head0_at_origin\"\n",
+    );
+    let mut machine = Machine::new(&model, "toml").unwrap();
+    machine.input("1");
+    assert!(!machine.run().unwrap());
+    assert_eq!(
+        machine.explain_verdict(),
+        "rejected: halted in final state `q0`, but `head0_at_origin` does not hold"
+    );
+}
@@ -1,4 +1,5 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use std::num::NonZeroUsize;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -19,4 +20,50 @@ pub struct Cli {
     /// The input string for the machine, if not provided, will be read from stdin.
     #[arg(short, long)]
     pub input: Option<String>,
-}
\ No newline at end of file
+
+    /// If provided, the tapes are read from this file instead of the input.
+    /// Tapes are separated by a `---` line, and a `^` before a symbol puts the head on it.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["input", "sep"])]
+    pub input_file: Option<String>,
+
+    /// If provided, the input is split by this separator into the contents of every tape.
+    #[arg(long)]
+    pub sep: Option<char>,
+
+    /// If provided, run the tests declared in the model instead of reading an input,
+    /// and exit with an error if any of them fails.
+    #[arg(short, long)]
+    pub test: bool,
+
+    /// The output format, `jsonl` prints one json object per step.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Pretty)]
+    pub format: OutputFormat,
+
+    /// If provided, run this machine too on every input of `--inputs-file`,
+    /// print the inputs where the outcomes or the tapes differ,
+    /// and exit with an error if any differ.
+    #[arg(long, value_name = "PATH", requires = "inputs_file")]
+    pub compare: Option<String>,
+
+    /// The inputs for `--compare`, one per line.
+    #[arg(long, value_name = "PATH", requires = "compare")]
+    pub inputs_file: Option<String>,
+
+    /// If provided, print only the content of the first tape, same as `--format raw`.
+    #[arg(long)]
+    pub raw: bool,
+
+    /// If provided, print the step count to stderr every this many steps.
+    #[arg(long, value_name = "STEPS")]
+    pub progress: Option<NonZeroUsize>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable state and tapes
+    Pretty,
+    /// JSON Lines, one object per step
+    Jsonl,
+    /// Only the content of the first tape
+    Raw,
+}
@@ -2,29 +2,175 @@ mod cli;
 mod trm_wrapper;
 
 use clap::Parser;
-pub use cli::Cli;
-pub use trm_wrapper::MachineWrapper;
+pub use cli::{Cli, OutputFormat};
+use std::io::BufRead;
+pub use trm_wrapper::*;
 
 pub fn run() {
     let cli = Cli::parse();
-    let mut machine = MachineWrapper::from_file(&cli.file, cli.ext.as_deref()).unwrap_or_else(|e| {
+    let machine = MachineWrapper::from_file(&cli.file, cli.ext.as_deref()).unwrap_or_else(|e| {
         eprintln!("{}", e);
         std::process::exit(1);
     });
+    let format = if cli.raw {
+        OutputFormat::Raw
+    } else {
+        cli.format
+    };
+    let empty = machine.machine().pattern_config().empty;
+    let formatter: Box<dyn MachineIdentifierFormatter> = match format {
+        OutputFormat::Pretty => Box::new(DefaultMachineIdentifierFormatter),
+        OutputFormat::Jsonl => Box::new(JsonLinesFormatter),
+        OutputFormat::Raw => Box::new(RawFormatter { empty }),
+    };
+    let machine = machine
+        .with_formatter(formatter)
+        .with_separator(cli.sep)
+        .with_progress(cli.progress);
 
-    let mut s = String::new();
-    let input = cli.input.as_deref().unwrap_or_else(|| {
-        std::io::stdin().read_line(&mut s).unwrap_or_else(|_| {
-            eprintln!("Failed to read from stdin");
+    if cli.test {
+        let (report, passed) = machine.run_tests();
+        print!("{}", report);
+        std::process::exit(if passed { 0 } else { 1 });
+    }
+
+    if let (Some(path), Some(inputs)) = (&cli.compare, &cli.inputs_file) {
+        let compared = MachineWrapper::from_file(path, None)
+            .map(|reference| machine.with_reference(Some(reference.machine().clone())))
+            .and_then(|machine| {
+                let inputs = std::fs::read_to_string(inputs).map_err(|e| e.to_string())?;
+                machine.compare(&inputs.lines().collect::<Vec<_>>())
+            });
+        let (report, same) = compared.unwrap_or_else(|e| {
+            eprintln!("{}", e);
             std::process::exit(1);
         });
-        // remove trailing newline
-        s.trim()
-    });
+        print!("{}", report);
+        std::process::exit(if same { 0 } else { 1 });
+    }
 
-    let output = machine.run(input, cli.verbose).unwrap_or_else(|e| {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    if let Some(path) = cli.input_file {
+        let tapes = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| parse_tapes(&text))
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            });
+        let tapes = tapes
+            .iter()
+            .map(|(t, head)| (t.as_str(), *head))
+            .collect::<Vec<_>>();
+        let mut machine = interruptible(machine);
+        machine
+            .run_tapes_to(&tapes, cli.verbose, &mut std::io::stdout())
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            });
+        if format == OutputFormat::Pretty {
+            println!();
+        }
+        return;
+    }
+
+    let input = cli.input.unwrap_or_else(|| {
+        read_input(&mut std::io::stdin().lock()).unwrap_or_else(|_| {
+            eprintln!("Failed to read from stdin");
+            std::process::exit(1);
+        })
     });
-    println!("{}", output);
-}
\ No newline at end of file
+
+    let mut machine = interruptible(machine);
+    machine
+        .run_to(&input, cli.verbose, &mut std::io::stdout())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+    // the pretty output ends with an empty line, the other formats end at their last line
+    if format == OutputFormat::Pretty {
+        println!();
+    }
+}
+
+/// stops the run on ctrl-c, printing where it was.
+/// Installed right before the run, so ctrl-c still exits everywhere else
+#[cfg(feature = "interrupt")]
+fn interruptible<F: MachineIdentifierFormatter>(machine: MachineWrapper<F>) -> MachineWrapper<F> {
+    let interrupt = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let flag = interrupt.clone();
+    match ctrlc::set_handler(move || flag.store(true, std::sync::atomic::Ordering::SeqCst)) {
+        Ok(()) => machine.with_interrupt(Some(interrupt)),
+        Err(e) => {
+            eprintln!("Failed to handle ctrl-c: {}", e);
+            machine
+        }
+    }
+}
+
+/// without the `interrupt` feature, ctrl-c exits as usual
+#[cfg(not(feature = "interrupt"))]
+fn interruptible<F: MachineIdentifierFormatter>(machine: MachineWrapper<F>) -> MachineWrapper<F> {
+    machine
+}
+
+/// reads one line of input, removing only its line ending,
+/// so spaces at either end stay part of the input
+pub fn read_input(reader: &mut impl BufRead) -> std::io::Result<String> {
+    let mut s = String::new();
+    reader.read_line(&mut s)?;
+    if s.ends_with('\n') {
+        s.pop();
+        if s.ends_with('\r') {
+            s.pop();
+        }
+    }
+    Ok(s)
+}
+
+/// the line separating the tapes in an input file
+pub const TAPE_SEPARATOR: &str = "---";
+
+/// the symbol before the one under the head in an input file
+pub const HEAD_MARKER: char = '^';
+
+/// parses an input file into the contents of every tape with the index of its head.
+/// Tapes are separated by a `---` line and hold one line each, an empty tape may have none.
+/// A `^` before a symbol puts the head on it, at the end on the blank after the content,
+/// without it the head is on the first symbol
+pub fn parse_tapes(text: &str) -> Result<Vec<(String, usize)>, String> {
+    let mut sections = vec![vec![]];
+    for line in text.lines() {
+        if line == TAPE_SEPARATOR {
+            sections.push(vec![]);
+        } else {
+            sections.last_mut().unwrap().push(line);
+        }
+    }
+    sections
+        .iter()
+        .enumerate()
+        .map(|(i, lines)| {
+            let line = match lines[..] {
+                [] => "",
+                [line] => line,
+                _ => return Err(format!("Tape {i} has {} lines, expected one.", lines.len())),
+            };
+            let content = line
+                .chars()
+                .filter(|c| *c != HEAD_MARKER)
+                .collect::<String>();
+            let head = match line.matches(HEAD_MARKER).count() {
+                0 => 0,
+                1 => line.chars().take_while(|c| *c != HEAD_MARKER).count(),
+                n => {
+                    return Err(format!(
+                        "Tape {i} has {n} head markers, expected at most one."
+                    ))
+                }
+            };
+            Ok((content, head))
+        })
+        .collect()
+}
@@ -1,53 +1,350 @@
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use trm_sim::trm;
-use trm_sim::trm::MachineIdentifier;
+use trm_sim::trm::{MachineIdentifier, RunOutcome};
+
+/// steps a declared test may take before it counts as failed
+pub const TEST_MAX_STEPS: usize = 100_000;
 
 pub struct MachineWrapper<Formatter: MachineIdentifierFormatter> {
     trm: trm::Machine,
     formatter: Formatter,
+    /// splits the input into the contents of every tape
+    separator: Option<char>,
+    /// reports the step count every this many steps
+    progress: Option<NonZeroUsize>,
+    /// the machine to compare with
+    reference: Option<trm::Machine>,
+    /// set to stop a run between two steps
+    interrupt: Option<Arc<AtomicBool>>,
 }
 
 impl MachineWrapper<DefaultMachineIdentifierFormatter> {
     pub fn from_file(path: &str, ext: Option<&str>) -> Result<Self, String> {
-        let ext = ext.or(path.split('.').last()).ok_or("No extension provided")?;
+        let ext = ext
+            .or(path.split('.').next_back())
+            .ok_or("No extension provided")?;
         let model_str = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
         let trm = trm::Machine::new(&model_str, ext).map_err(|e| e.to_string())?;
-        Ok(Self { trm, formatter: DefaultMachineIdentifierFormatter })
+        let formatter = DefaultMachineIdentifierFormatter;
+        let (separator, progress, reference, interrupt) = (None, None, None, None);
+        Ok(Self {
+            trm,
+            formatter,
+            separator,
+            progress,
+            reference,
+            interrupt,
+        })
+    }
+}
+
+impl<Formatter: MachineIdentifierFormatter> MachineWrapper<Formatter> {
+    /// use another formatter for the output
+    pub fn with_formatter<F: MachineIdentifierFormatter>(self, formatter: F) -> MachineWrapper<F> {
+        let Self {
+            trm,
+            separator,
+            progress,
+            reference,
+            interrupt,
+            ..
+        } = self;
+        MachineWrapper {
+            trm,
+            formatter,
+            separator,
+            progress,
+            reference,
+            interrupt,
+        }
+    }
+
+    /// get the wrapped machine
+    pub fn machine(&self) -> &trm::Machine {
+        &self.trm
+    }
+
+    /// split inputs by the separator into the contents of every tape
+    pub fn with_separator(self, separator: Option<char>) -> Self {
+        Self { separator, ..self }
+    }
+
+    /// report the step count every `every` steps while running
+    pub fn with_progress(self, every: Option<NonZeroUsize>) -> Self {
+        Self {
+            progress: every,
+            ..self
+        }
+    }
+
+    /// stop runs once the flag is set, printing where the run was
+    pub fn with_interrupt(self, interrupt: Option<Arc<AtomicBool>>) -> Self {
+        Self { interrupt, ..self }
+    }
+
+    /// compare with the reference machine in `compare`
+    pub fn with_reference(self, reference: Option<trm::Machine>) -> Self {
+        Self { reference, ..self }
+    }
+
+    /// run both the machine and the reference on every input,
+    /// for at most `TEST_MAX_STEPS` steps each,
+    /// returns the report of the inputs where the outcomes or the tapes differ,
+    /// and whether none differ
+    pub fn compare(&self, inputs: &[&str]) -> Result<(String, bool), String> {
+        let mut reference = self.reference.clone().ok_or("No machine to compare with")?;
+        let mut machine = self.trm.clone();
+        let run = |machine: &mut trm::Machine, input: &str| {
+            machine.reset();
+            machine.input(input);
+            let outcome = machine
+                .run_with_limit(TEST_MAX_STEPS)
+                .map_err(|e| e.to_string());
+            (outcome, machine.identifier().tape)
+        };
+        let describe = |(outcome, tapes): &(Result<RunOutcome, String>, Vec<trm::FrozenTape>)| {
+            let outcome = outcome
+                .as_ref()
+                .map_or_else(|e| e.clone(), |o| format!("{o:?}"));
+            let tapes = tapes.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+            format!("{outcome} with tapes `{}`", tapes.join(" | "))
+        };
+        let mut report = String::new();
+        let mut differ = 0;
+        for input in inputs {
+            let this = run(&mut machine, input);
+            let other = run(&mut reference, input);
+            if this != other {
+                differ += 1;
+                let (this, other) = (describe(&this), describe(&other));
+                report.push_str(&format!("DIFF `{input}`: {this}, reference {other}\n"));
+            }
+        }
+        report.push_str(&format!(
+            "{} same, {differ} different\n",
+            inputs.len() - differ
+        ));
+        Ok((report, differ == 0))
     }
 
     pub fn run(&mut self, input: &str, verbose: bool) -> Result<String, String> {
+        let mut out = Vec::new();
+        self.run_to(input, verbose, &mut out)?;
+        String::from_utf8(out).map_err(|e| e.to_string())
+    }
+
+    /// run the machine on the contents of every tape with the index of its head, like `run`
+    pub fn run_tapes(&mut self, tapes: &[(&str, usize)], verbose: bool) -> Result<String, String> {
+        let mut out = Vec::new();
+        self.run_tapes_to(tapes, verbose, &mut out)?;
+        String::from_utf8(out).map_err(|e| e.to_string())
+    }
+
+    /// run the tests declared in the model,
+    /// returns the report and whether all tests passed
+    pub fn run_tests(&self) -> (String, bool) {
+        let tests = self.trm.tests();
+        let examples = tests
+            .iter()
+            .map(|t| {
+                let outcome = if t.accept {
+                    RunOutcome::Accepted
+                } else {
+                    RunOutcome::Rejected
+                };
+                (t.input.as_str(), outcome)
+            })
+            .collect::<Vec<_>>();
+        let mismatches = self
+            .trm
+            .check_examples(&examples, TEST_MAX_STEPS)
+            .err()
+            .unwrap_or_default();
+        let mut report = String::new();
+        for m in &mismatches {
+            let actual = m
+                .actual
+                .map_or("an error".to_string(), |a| format!("{a:?}"));
+            let line = format!(
+                "FAIL `{}`: expected {:?}, got {actual}\n",
+                m.input, m.expected
+            );
+            report.push_str(&line);
+        }
+        report.push_str(&format!(
+            "{} passed, {} failed\n",
+            tests.len() - mismatches.len(),
+            mismatches.len()
+        ));
+        (report, mismatches.is_empty())
+    }
+
+    /// run the machine, writing the output as soon as each step is formatted,
+    /// and the progress to stderr
+    pub fn run_to(
+        &mut self,
+        input: &str,
+        verbose: bool,
+        out: &mut impl Write,
+    ) -> Result<(), String> {
+        self.run_reporting(input, verbose, out, &mut std::io::stderr())
+    }
+
+    /// run the machine on the contents of every tape with the index of its head, like `run_to`
+    pub fn run_tapes_to(
+        &mut self,
+        tapes: &[(&str, usize)],
+        verbose: bool,
+        out: &mut impl Write,
+    ) -> Result<(), String> {
         self.trm.reset();
-        self.trm.input(input);
-        let mut s = String::new();
-        if !verbose {
-            self.trm.run().map_err(|e| e.to_string())?;
-            s.push_str(self.formatter.format(self.trm.identifier()).as_str());
-        } else {
-            while !self.trm.run_once().map_err(|e| e.to_string())? {
-                s.push_str(self.formatter.format(self.trm.identifier()).as_str());
+        self.trm
+            .input_with_heads(tapes)
+            .map_err(|e| e.to_string())?;
+        self.run_loaded(verbose, out, &mut std::io::stderr())
+    }
+
+    /// run the machine like `run_to`, writing the progress lines to `progress`
+    pub fn run_reporting(
+        &mut self,
+        input: &str,
+        verbose: bool,
+        out: &mut impl Write,
+        progress: &mut impl Write,
+    ) -> Result<(), String> {
+        self.trm.reset();
+        match self.separator {
+            Some(sep) => self
+                .trm
+                .input_delimited(input, sep)
+                .map_err(|e| e.to_string())?,
+            None => self.trm.input(input),
+        }
+        self.run_loaded(verbose, out, progress)
+    }
+
+    /// run the machine from the tapes already given.
+    /// If interrupted, the current step is written and the error tells the step count
+    fn run_loaded(
+        &mut self,
+        verbose: bool,
+        out: &mut impl Write,
+        progress: &mut impl Write,
+    ) -> Result<(), String> {
+        let mut write = |s: String| out.write_all(s.as_bytes()).map_err(|e| e.to_string());
+        let mut step = 0;
+        loop {
+            if self
+                .interrupt
+                .as_ref()
+                .is_some_and(|i| i.load(Ordering::SeqCst))
+            {
+                // a verbose run has written the current step already
+                if !verbose || step == 0 {
+                    write(self.formatter.format_step(step, self.trm.identifier()))?;
+                }
+                return Err(format!("Interrupted after {step} steps."));
             }
+            if self.trm.run_once().map_err(|e| e.to_string())? {
+                break;
+            }
+            step += 1;
+            if verbose {
+                if let Some(note) = self.trm.last_transition().and_then(|t| t.note.as_deref()) {
+                    write(self.formatter.format_note(note))?;
+                }
+                write(self.formatter.format_step(step, self.trm.identifier()))?;
+            }
+            if self.progress.is_some_and(|n| step % n == 0) {
+                writeln!(progress, "-- step {step}").map_err(|e| e.to_string())?;
+            }
+        }
+        if !verbose {
+            write(self.formatter.format_step(step, self.trm.identifier()))?;
         }
 
-        Ok(s)
+        Ok(())
     }
 }
 
 pub trait MachineIdentifierFormatter {
     fn format(&self, id: MachineIdentifier) -> String;
+
+    /// format the identifier after the given number of steps,
+    /// the step is ignored by default
+    fn format_step(&self, _step: usize, id: MachineIdentifier) -> String {
+        self.format(id)
+    }
+
+    /// format the note of a transition, printed before the step it is taken in
+    fn format_note(&self, note: &str) -> String {
+        format!("-- note: {note}\n")
+    }
+}
+
+impl<F: MachineIdentifierFormatter + ?Sized> MachineIdentifierFormatter for Box<F> {
+    fn format(&self, id: MachineIdentifier) -> String {
+        (**self).format(id)
+    }
+
+    fn format_step(&self, step: usize, id: MachineIdentifier) -> String {
+        (**self).format_step(step, id)
+    }
+
+    fn format_note(&self, note: &str) -> String {
+        (**self).format_note(note)
+    }
 }
 
 pub struct DefaultMachineIdentifierFormatter;
 
 impl MachineIdentifierFormatter for DefaultMachineIdentifierFormatter {
     fn format(&self, id: MachineIdentifier) -> String {
-        let state = id.current_state;
-        let tapes = id.tape;
-        let mut s = String::new();
-        s.push_str(format!("State: {}\n", state).as_str());
-        for (i, tape) in tapes.iter().enumerate() {
-            s.push_str(format!("Tape {}: {}\n", i, tape.tape).as_str());
-            s.push_str(format!("Head {}: {}\n", i, tape.head).as_str());
-            s.push_str(format!("Range ({}..{})\n", tape.range.start, tape.range.end).as_str());
-        }
-        s
+        id.to_string_pretty()
     }
-}
\ No newline at end of file
+}
+
+/// one json object per line, like `{"step":1,"state":"q0","tapes":[...]}`
+pub struct JsonLinesFormatter;
+
+impl MachineIdentifierFormatter for JsonLinesFormatter {
+    fn format(&self, id: MachineIdentifier) -> String {
+        self.format_step(0, id)
+    }
+
+    fn format_step(&self, step: usize, id: MachineIdentifier) -> String {
+        // written by hand to keep `step` first, json maps sort their keys
+        let state = serde_json::to_string(&id.current_state).unwrap();
+        let tapes = serde_json::to_string(&id.tape).unwrap();
+        format!("{{\"step\":{step},\"state\":{state},\"tapes\":{tapes}}}\n")
+    }
+
+    /// notes are left out, every line must stay a step object
+    fn format_note(&self, _note: &str) -> String {
+        String::new()
+    }
+}
+
+/// only the content of the first tape, without the blanks at both ends
+pub struct RawFormatter {
+    /// the blank symbol of the machine
+    pub empty: char,
+}
+
+impl MachineIdentifierFormatter for RawFormatter {
+    fn format(&self, id: MachineIdentifier) -> String {
+        let tape = id
+            .tape
+            .first()
+            .map_or("", |t| t.tape.trim_matches(self.empty));
+        format!("{tape}\n")
+    }
+
+    /// notes are left out, the output is only the tape
+    fn format_note(&self, _note: &str) -> String {
+        String::new()
+    }
+}
@@ -0,0 +1,46 @@
+//! Test the cli `--compare` mode against a reference machine
+
+use std::process::Command;
+
+const PROGRAMS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../turing-programs");
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+/// run the cli comparing a machine to bit flip, returns the output and if it succeed
+fn run_compare(file: &str) -> (String, bool) {
+    let output = Command::new(env!("CARGO_BIN_EXE_trm_sim_cli"))
+        .args([
+            "-f",
+            file,
+            "--compare",
+            &format!("{PROGRAMS}/bit_flip.toml"),
+        ])
+        .args(["--inputs-file", &format!("{FIXTURES}/compare_inputs.txt")])
+        .output()
+        .unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        output.status.success(),
+    )
+}
+
+/// test the inputs a broken copy writes other tapes on are reported
+#[test]
+fn test_compare_broken_copy() {
+    let (report, success) = run_compare(&format!("{FIXTURES}/bit_flip_broken.toml"));
+    assert!(!success);
+    assert_eq!(
+        report,
+        "DIFF `01`: Accepted with tapes `11[_]`, reference Accepted with tapes `10[_]`\n\
+         DIFF `10`: Accepted with tapes `11[_]`, reference Accepted with tapes `01[_]`\n\
+         2 same, 2 different\n"
+    );
+}
+
+/// test a machine is the same as itself
+#[test]
+fn test_compare_same_machine() {
+    let (report, success) = run_compare(&format!("{PROGRAMS}/bit_flip.toml"));
+    assert!(success);
+    assert_eq!(report, "4 same, 0 different\n");
+}
@@ -0,0 +1,46 @@
+//! Test reading the input from stdin or a file
+
+use trm_sim_cli::{parse_tapes, read_input, JsonLinesFormatter, MachineWrapper};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/three_tapes");
+
+/// test only the line ending is removed from the input
+#[test]
+fn test_read_input() {
+    assert_eq!(read_input(&mut " 01 \n".as_bytes()).unwrap(), " 01 ");
+    assert_eq!(read_input(&mut " 01 \r\n".as_bytes()).unwrap(), " 01 ");
+    assert_eq!(read_input(&mut "01".as_bytes()).unwrap(), "01");
+    assert_eq!(read_input(&mut "01\n\n".as_bytes()).unwrap(), "01");
+    assert_eq!(read_input(&mut "".as_bytes()).unwrap(), "");
+}
+
+/// test an input file gives the content and head of every tape
+#[test]
+fn test_input_file() {
+    let text = std::fs::read_to_string(format!("{FIXTURE}.txt")).unwrap();
+    let tapes = parse_tapes(&text).unwrap();
+    let expected = [("abc", 2), ("", 0), ("01", 2)];
+    assert_eq!(tapes, expected.map(|(t, head)| (t.to_string(), head)));
+
+    let mut machine = MachineWrapper::from_file(&format!("{FIXTURE}.toml"), None)
+        .unwrap()
+        .with_formatter(JsonLinesFormatter);
+    let output = machine.run_tapes(&expected, false).unwrap();
+    let line = serde_json::from_str::<serde_json::Value>(output.trim_end()).unwrap();
+    // the heads are at the outside index 0, the contents start left of them
+    for (i, (tape, head)) in [("abc", 2), ("_", 0), ("01_", 2)].into_iter().enumerate() {
+        assert_eq!(line["tapes"][i]["tape"], tape);
+        assert_eq!(line["tapes"][i]["head"], 0);
+        assert_eq!(line["tapes"][i]["range"]["start"], -(head as isize));
+    }
+}
+
+/// test malformed input files are errors
+#[test]
+fn test_input_file_errors() {
+    assert_eq!(parse_tapes("").unwrap(), [(String::new(), 0)]);
+    assert!(parse_tapes("a^b^c").unwrap_err().contains("2 head markers"));
+    assert!(parse_tapes("ab\ncd\n---\n")
+        .unwrap_err()
+        .contains("Tape 0 has 2 lines"));
+}
@@ -0,0 +1,39 @@
+//! Test the cli `--test` mode on models with declared tests
+
+use std::process::Command;
+
+const PROGRAMS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../turing-programs");
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+/// run the cli in test mode on a program file, returns the output and if it succeed
+fn run_test_mode(file: &str) -> (String, bool) {
+    let output = Command::new(env!("CARGO_BIN_EXE_trm_sim_cli"))
+        .args(["--test", "-f", file])
+        .output()
+        .unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        output.status.success(),
+    )
+}
+
+/// test a failing declared test is reported and fails the run
+#[test]
+fn test_failing_model_test() {
+    let (report, success) = run_test_mode(&format!("{FIXTURES}/bit_flip_tests.toml"));
+    assert!(!success);
+    assert!(
+        report.contains("FAIL `01a`: expected Rejected, got Accepted"),
+        "{report}"
+    );
+    assert!(report.ends_with("1 passed, 1 failed\n"), "{report}");
+}
+
+/// test a model without tests passes
+#[test]
+fn test_no_model_tests() {
+    let (report, success) = run_test_mode(&format!("{PROGRAMS}/bit_flip.toml"));
+    assert!(success);
+    assert_eq!(report, "0 passed, 0 failed\n");
+}
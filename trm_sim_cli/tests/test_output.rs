@@ -0,0 +1,151 @@
+//! Test the output formats of the cli
+
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use trm_sim_cli::{JsonLinesFormatter, MachineWrapper};
+
+const BIT_FLIP: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../turing-programs/bit_flip.toml"
+);
+
+/// test every jsonl line is a json object and the steps increase
+#[test]
+fn test_json_lines() {
+    let mut machine = MachineWrapper::from_file(BIT_FLIP, None)
+        .unwrap()
+        .with_formatter(JsonLinesFormatter);
+    let output = machine.run("0110", true).unwrap();
+
+    let lines = output
+        .lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    for (i, line) in lines.iter().enumerate() {
+        assert_eq!(line["step"], i + 1);
+        assert_eq!(line["state"], "q0");
+    }
+    assert_eq!(lines[3]["tapes"][0]["head"], 4);
+
+    let output = machine.run("0110", false).unwrap();
+    assert_eq!(output.lines().count(), 1);
+    assert!(output.contains("\"step\":4"));
+}
+
+/// test the note of a transition is printed before the step taking it
+#[test]
+fn test_transition_note() {
+    let mut machine = MachineWrapper::from_file(BIT_FLIP, None).unwrap();
+    let output = machine.run("010", true).unwrap();
+    let steps = output.split("State: ").collect::<Vec<_>>();
+    // nothing before the first step, then one step per symbol,
+    // the note of the second step ends the first one
+    assert_eq!(steps.len(), 4);
+    assert_eq!(steps[0], "");
+    assert!(
+        steps[1].ends_with("-- note: a one becomes a zero\n"),
+        "{output}"
+    );
+    assert!(!steps[2].contains("note"));
+    assert!(!steps[3].contains("note"));
+
+    let output = machine.run("010", false).unwrap();
+    assert!(!output.contains("note"));
+}
+
+/// test a separated input fails on a machine with fewer tapes
+#[test]
+fn test_separated_input() {
+    let mut machine = MachineWrapper::from_file(BIT_FLIP, None)
+        .unwrap()
+        .with_separator(Some('|'));
+    assert!(machine.run("01", false).is_ok());
+    let err = machine.run("01|10", false).err().unwrap();
+    assert!(err.contains("Input has 2 tapes"), "{err}");
+}
+
+/// test `--raw` prints only the first tape
+#[test]
+fn test_raw_output() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_trm_sim_cli"))
+        .args(["--raw", "-f", BIT_FLIP, "-i", "000000"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "111111\n");
+}
+
+/// test the pretty output ends with an empty line
+#[test]
+fn test_pretty_output_end() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_trm_sim_cli"))
+        .args(["-f", BIT_FLIP, "-i", "000000"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().ends_with(")\n\n"));
+}
+
+/// test the progress is reported every given number of steps, apart from the output
+#[test]
+fn test_progress() {
+    let mut machine = MachineWrapper::from_file(BIT_FLIP, None)
+        .unwrap()
+        .with_progress(NonZeroUsize::new(3));
+    let (mut out, mut progress) = (Vec::new(), Vec::new());
+    machine
+        .run_reporting("0110100", false, &mut out, &mut progress)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(progress).unwrap(),
+        "-- step 3\n-- step 6\n"
+    );
+    let out = String::from_utf8(out).unwrap();
+    assert!(!out.contains("-- step"));
+    assert!(out.starts_with("State: q0\nTape 0: 1001011[_]\n"));
+
+    let mut machine = machine.with_progress(None);
+    let mut progress = Vec::new();
+    machine
+        .run_reporting("0110100", true, &mut Vec::new(), &mut progress)
+        .unwrap();
+    assert!(progress.is_empty());
+}
+
+/// sets the flag on the first progress line, like a ctrl-c in the middle of a run
+struct InterruptOnProgress(Arc<AtomicBool>);
+
+impl std::io::Write for InterruptOnProgress {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.store(true, Ordering::SeqCst);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// test an interrupted run writes where it was and tells the step count
+#[test]
+fn test_interrupted_run() {
+    let interrupt = Arc::new(AtomicBool::new(false));
+    let mut machine = MachineWrapper::from_file(BIT_FLIP, None)
+        .unwrap()
+        .with_progress(NonZeroUsize::new(3))
+        .with_interrupt(Some(interrupt.clone()));
+    let mut out = Vec::new();
+    let error = machine
+        .run_reporting(
+            "0110100",
+            false,
+            &mut out,
+            &mut InterruptOnProgress(interrupt),
+        )
+        .unwrap_err();
+    assert_eq!(error, "Interrupted after 3 steps.");
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("State: q0\nTape 0: 100[0]100\n"), "{out}");
+}